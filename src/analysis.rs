@@ -0,0 +1,236 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::grid::{Grid, Tile, TileType, Walkability, Zone};
+
+// How the ghost house connects to the maze
+#[derive(Debug, PartialEq)]
+pub enum GhostHouseStatus {
+    // Ghosts get out through a GhostGate, Pacman cannot get in
+    Connected,
+    // The map has no ghost house marker (H)
+    Missing,
+    // Ghosts cannot reach the Pacman spawn from the house
+    Sealed,
+    // Pacman can walk into the house, the gate does not close it
+    Open,
+}
+
+// Summary of a map, used by map authors to find broken layouts without playing them
+pub struct MapReport {
+    pub width: i32,
+    pub height: i32,
+    pub pellet_count: usize,
+    pub power_pellet_count: usize,
+    pub intersection_count: usize,
+    pub dead_ends: Vec<(i32, i32)>,
+    pub tunnel_pairs: Vec<((i32, i32), (i32, i32))>,
+    pub tunnel_zone_count: usize,
+    pub red_zone_count: usize,
+    pub unreachable_pellets: Vec<(i32, i32)>,
+    pub ghost_house: GhostHouseStatus,
+    pub has_pacman_spawn: bool,
+}
+
+impl MapReport {
+    // Problems that make the map unplayable
+    pub fn has_errors(&self) -> bool {
+        !self.has_pacman_spawn
+            || !self.unreachable_pellets.is_empty()
+            || self.ghost_house == GhostHouseStatus::Sealed
+            || self.ghost_house == GhostHouseStatus::Open
+    }
+}
+
+fn walkable_neighbors(grid: &Grid, pos: (i32, i32)) -> usize {
+    grid.walkable_neighbors(pos, Walkability::Pacman).count()
+}
+
+fn ghost_house_status(grid: &Grid, pacman_reachable: &HashSet<(i32, i32)>) -> GhostHouseStatus {
+    let Some(house) = grid.markers().ghost_house else {
+        return GhostHouseStatus::Missing;
+    };
+    let Some(pacman_spawn) = grid.markers().pacman_spawn else {
+        return GhostHouseStatus::Sealed;
+    };
+
+    if pacman_reachable.contains(&house) {
+        return GhostHouseStatus::Open;
+    }
+
+    if grid.reachable_from(house, Tile::is_walkable_for_ghost).contains(&pacman_spawn) {
+        GhostHouseStatus::Connected
+    } else {
+        GhostHouseStatus::Sealed
+    }
+}
+
+// Pairs of edge tiles Pacman can reach, joined by the wraparound
+fn tunnel_pairs(grid: &Grid, pacman_reachable: &HashSet<(i32, i32)>) -> Vec<((i32, i32), (i32, i32))> {
+    let walkable = |pos: (i32, i32)| pacman_reachable.contains(&pos);
+    let mut pairs = Vec::new();
+
+    for row in 0..grid.height() {
+        let (left, right) = ((0, row), (grid.width() - 1, row));
+        if walkable(left) && walkable(right) {
+            pairs.push((left, right));
+        }
+    }
+
+    for col in 0..grid.width() {
+        let (top, bottom) = ((col, 0), (col, grid.height() - 1));
+        if walkable(top) && walkable(bottom) {
+            pairs.push((top, bottom));
+        }
+    }
+
+    pairs
+}
+
+pub fn analyze_map(grid: &Grid) -> MapReport {
+    let pacman_spawn = grid.markers().pacman_spawn;
+
+    // Without a spawn every walkable tile is considered for the layout checks
+    let pacman_reachable: HashSet<(i32, i32)> = match pacman_spawn {
+        Some(spawn) => grid.reachable_from(spawn, Tile::is_walkable_for_pacman),
+        None => grid.get_tiles().iter().filter(|tile| tile.is_walkable_for_pacman()).map(|tile| tile.pos).collect(),
+    };
+
+    let mut dead_ends = Vec::new();
+    let mut intersection_count = 0;
+    let mut pellet_count = 0;
+    let mut power_pellet_count = 0;
+    let mut tunnel_zone_count = 0;
+    let mut red_zone_count = 0;
+
+    // Transposed so that tiles are visited in reading order
+    for tile in grid.get_tiles().t() {
+        match tile.type_ {
+            TileType::Pellet => pellet_count += 1,
+            TileType::PowerPellet => power_pellet_count += 1,
+            _ => {}
+        }
+
+        match tile.zone {
+            Zone::Tunnel => tunnel_zone_count += 1,
+            Zone::RedZone => red_zone_count += 1,
+            Zone::None => {}
+        }
+
+        if !pacman_reachable.contains(&tile.pos) {
+            continue;
+        }
+
+        match walkable_neighbors(grid, tile.pos) {
+            0 | 1 => dead_ends.push(tile.pos),
+            2 => {},
+            _ => intersection_count += 1,
+        }
+    }
+
+    MapReport {
+        width: grid.width(),
+        height: grid.height(),
+        pellet_count,
+        power_pellet_count,
+        intersection_count,
+        dead_ends,
+        tunnel_pairs: tunnel_pairs(grid, &pacman_reachable),
+        tunnel_zone_count,
+        red_zone_count,
+        unreachable_pellets: pacman_spawn.map(|spawn| grid.unreachable_pellets(spawn)).unwrap_or_default(),
+        ghost_house: ghost_house_status(grid, &pacman_reachable),
+        has_pacman_spawn: pacman_spawn.is_some(),
+    }
+}
+
+// Positions are printed as 1-based line/column like the map loading errors
+fn write_positions(f: &mut fmt::Formatter, positions: impl Iterator<Item = (i32, i32)>) -> fmt::Result {
+    for (col, row) in positions {
+        write!(f, " ({}:{})", row + 1, col + 1)?;
+    }
+    writeln!(f)
+}
+
+impl fmt::Display for MapReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Size: {}x{}", self.width, self.height)?;
+        writeln!(f, "Pellets: {} ({} power pellets)", self.pellet_count + self.power_pellet_count, self.power_pellet_count)?;
+        writeln!(f, "Intersections: {}", self.intersection_count)?;
+
+        write!(f, "Dead ends: {}", self.dead_ends.len())?;
+        write_positions(f, self.dead_ends.iter().copied())?;
+
+        write!(f, "Tunnel pairs: {}", self.tunnel_pairs.len())?;
+        for ((a_col, a_row), (b_col, b_row)) in &self.tunnel_pairs {
+            write!(f, " ({}:{} <-> {}:{})", a_row + 1, a_col + 1, b_row + 1, b_col + 1)?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "Zones: {} tunnel tiles, {} red zone tiles", self.tunnel_zone_count, self.red_zone_count)?;
+
+        let house = match self.ghost_house {
+            GhostHouseStatus::Connected => "connected through the ghost gate",
+            GhostHouseStatus::Missing => "no ghost house marker (H)",
+            GhostHouseStatus::Sealed => "ERROR: ghosts cannot leave the house",
+            GhostHouseStatus::Open => "ERROR: Pacman can walk into the house",
+        };
+        writeln!(f, "Ghost house: {}", house)?;
+
+        if !self.has_pacman_spawn {
+            writeln!(f, "ERROR: the map has no Pacman spawn (P)")?;
+        }
+
+        if !self.unreachable_pellets.is_empty() {
+            write!(f, "ERROR: unreachable pellets: {}", self.unreachable_pellets.len())?;
+            write_positions(f, self.unreachable_pellets.iter().copied())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_file;
+
+    // A house under the top corridor, a tunnel on its row and two stubs at the bottom
+    const MAZE: &str = "#########\n#P.....o#\n#.##=##.#\n..#H  #..\n#.#####.#\n#...#...#\n#########\n";
+
+    fn analyze(name: &str, map: &str) -> MapReport {
+        analyze_map(&Grid::load(&test_file(name, map), 16).unwrap())
+    }
+
+    #[test]
+    fn reports_the_layout() {
+        let report = analyze("analysis-maze.map", MAZE);
+        assert_eq!((report.width, report.height), (9, 7));
+        assert_eq!((report.pellet_count, report.power_pellet_count), (19, 1));
+        assert_eq!(report.dead_ends, vec![(3, 5), (5, 5)]);
+        assert_eq!(report.tunnel_pairs, vec![((0, 3), (8, 3))]);
+        assert_eq!(report.intersection_count, 2);
+        assert_eq!(report.ghost_house, GhostHouseStatus::Connected);
+        assert!(!report.has_errors());
+    }
+
+    #[test]
+    fn checks_the_ghost_house() {
+        let open = analyze("analysis-open.map", &MAZE.replace('=', " "));
+        assert_eq!(open.ghost_house, GhostHouseStatus::Open);
+        let sealed = analyze("analysis-sealed.map", &MAZE.replace('=', "#"));
+        assert_eq!(sealed.ghost_house, GhostHouseStatus::Sealed);
+        let missing = analyze("analysis-missing.map", &MAZE.replace('H', " "));
+        assert_eq!(missing.ghost_house, GhostHouseStatus::Missing);
+        assert!(open.has_errors() && sealed.has_errors() && !missing.has_errors());
+    }
+
+    #[test]
+    fn reports_a_missing_spawn_and_unreachable_pellets() {
+        let report = analyze("analysis-broken.map", &MAZE.replace('P', ".").replace("#H  #", "#H. #"));
+        assert!(!report.has_pacman_spawn && report.has_errors());
+
+        let report = analyze("analysis-unreachable.map", &MAZE.replace("#H  #", "#H. #").replace('=', "#"));
+        assert_eq!(report.unreachable_pellets, vec![(4, 3)]);
+    }
+}
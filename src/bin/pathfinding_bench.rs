@@ -0,0 +1,50 @@
+use std::env;
+use std::time::{Duration, Instant};
+
+use pacman_rs::constants::{CELL_SIZE, MAP_FILE};
+use pacman_rs::grid::{Grid, Walkability};
+use pacman_rs::pathfinding::AStar;
+
+// Measures AStar on a map: paths from every walkable tile to every 7th walkable tile, repeated for a few seconds.
+// Run it in release mode: cargo run --release --bin pathfinding_bench [map]
+fn main() {
+    let map_path = env::args().nth(1).unwrap_or_else(|| MAP_FILE.to_string());
+    let grid = match Grid::new(&map_path, CELL_SIZE) {
+        Ok(grid) => grid,
+        Err(e) => {
+            eprintln!("Failed to load map {}: {}", map_path, e);
+            std::process::exit(1);
+        }
+    };
+
+    let walkable: Vec<(i32, i32)> = grid.get_tiles()
+        .t()
+        .iter()
+        .filter(|tile| tile.is_walkable_for_ghost())
+        .map(|tile| tile.pos)
+        .collect();
+
+    let mut astar = AStar::new(&grid);
+    let duration = Duration::from_secs(3);
+    let started = Instant::now();
+    let mut paths = 0u64;
+    let mut found = 0u64;
+    let mut total_length = 0u64;
+
+    while started.elapsed() < duration {
+        for &start in &walkable {
+            for &goal in walkable.iter().step_by(7) {
+                if let Some(path) = astar.find_path(start, goal, &grid, Walkability::EatenGhost) {
+                    found += 1;
+                    total_length += path.len() as u64;
+                }
+                paths += 1;
+            }
+        }
+    }
+
+    let elapsed = started.elapsed().as_secs_f64();
+    println!("Map: {} ({} walkable tiles)", map_path, walkable.len());
+    println!("Paths: {} in {:.2} s ({} found, average length {:.1})", paths, elapsed, found, total_length as f64 / found.max(1) as f64);
+    println!("Paths/second: {:.0}", paths as f64 / elapsed);
+}
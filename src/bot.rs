@@ -0,0 +1,64 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::enums::{Direction, GhostState, HouseState};
+use crate::game::Game;
+use crate::grid::Walkability;
+use crate::constants::BOT_DANGER_RADIUS;
+use crate::utils::manhattan_distance;
+
+// Attract mode player: walks to the closest pellet or frightened ghost, keeping clear of
+// the other ghosts. It only reads the game, its moves go through handle_input like the keyboard
+pub fn choose_direction(game: &Game) -> Option<Direction> {
+    let grid = game.grid();
+    let start = game.pacman().pos;
+
+    let dangerous: Vec<(i32, i32)> = game
+        .ghosts()
+        .iter()
+        .filter(|ghost| ghost.house == HouseState::Out && matches!(ghost.state, GhostState::Chase | GhostState::Scatter))
+        .map(|ghost| ghost.pos)
+        .collect();
+    let is_safe = |pos: (i32, i32)| dangerous.iter().all(|&ghost| manhattan_distance(pos, ghost) > BOT_DANGER_RADIUS);
+    let is_goal = |pos: (i32, i32)| {
+        grid.has_pellet(pos)
+            || game.ghosts().iter().any(|ghost| ghost.state == GhostState::Frightened && ghost.pos == pos)
+    };
+
+    // Breadth first search over the safe tiles, remembering the first move of each path
+    let directions = [Direction::Up, Direction::Left, Direction::Down, Direction::Right];
+    let mut visited = HashSet::from([start]);
+    let mut queue = VecDeque::new();
+    for direction in directions {
+        let next = grid.next_pos(start, direction);
+        let walkable = grid.get_tile(next).is_some_and(|tile| Walkability::Pacman.allows(tile));
+        if walkable && is_safe(next) && visited.insert(next) {
+            queue.push_back((next, direction));
+        }
+    }
+
+    while let Some((pos, first)) = queue.pop_front() {
+        if is_goal(pos) {
+            return Some(first);
+        }
+        for next in grid.walkable_neighbors(pos, Walkability::Pacman) {
+            if is_safe(next) && visited.insert(next) {
+                queue.push_back((next, first));
+            }
+        }
+    }
+
+    // Cornered: step to the tile furthest from the closest ghost
+    directions
+        .into_iter()
+        .filter(|&direction| {
+            let next = grid.next_pos(start, direction);
+            grid.get_tile(next).is_some_and(|tile| Walkability::Pacman.allows(tile))
+        })
+        .max_by(|&a, &b| {
+            let distance = |direction| {
+                let next = grid.next_pos(start, direction);
+                dangerous.iter().map(|&ghost| manhattan_distance(next, ghost)).fold(f32::MAX, f32::min)
+            };
+            distance(a).total_cmp(&distance(b))
+        })
+}
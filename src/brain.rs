@@ -0,0 +1,118 @@
+use crate::enums::Direction;
+use crate::game::Game;
+use crate::ghost::Ghost;
+use crate::utils::manhattan_distance;
+
+// What a brain wants the ghost to do on its next move
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BrainDecision {
+    // Head for a tile, the usual arcade targeting picks the way
+    Target((i32, i32)),
+    // Take this direction if it is legal, keep going otherwise
+    Direction(Direction),
+}
+
+// The personality of a ghost: where it goes in Chase mode. Scatter, frightened and
+// eaten ghosts follow the game rules whatever their brain.
+// The game is only given as a read-only view, through its getters.
+pub trait GhostBrain {
+    fn chase(&self, ghost: &Ghost, game: &Game) -> BrainDecision;
+}
+
+fn ahead_of_pacman(game: &Game, tiles: i32) -> (i32, i32) {
+    let pacman = game.pacman();
+    let (dx, dy) = match pacman.direction {
+        Direction::Left => (-1, 0),
+        Direction::Right => (1, 0),
+        Direction::Up => (0, -1),
+        Direction::Down => (0, 1),
+    };
+    (pacman.pos.0 + tiles * dx, pacman.pos.1 + tiles * dy)
+}
+
+// Blinky: goes straight for Pacman
+pub struct Shadow;
+
+impl GhostBrain for Shadow {
+    fn chase(&self, _ghost: &Ghost, game: &Game) -> BrainDecision {
+        BrainDecision::Target(game.pacman().pos)
+    }
+}
+
+// Pinky: aims four tiles in front of Pacman to cut him off
+pub struct Speedy;
+
+impl GhostBrain for Speedy {
+    fn chase(&self, _ghost: &Ghost, game: &Game) -> BrainDecision {
+        BrainDecision::Target(ahead_of_pacman(game, 4))
+    }
+}
+
+// Inky: two tiles in front of Pacman, pushed away from its partner (Blinky) by twice
+// the vector from the partner to that tile
+pub struct Bashful {
+    pub partner: String,
+}
+
+impl GhostBrain for Bashful {
+    fn chase(&self, _ghost: &Ghost, game: &Game) -> BrainDecision {
+        let ahead = ahead_of_pacman(game, 2);
+        // The roster partner is checked when the game starts, a brain set later without one
+        // just aims in front of Pacman
+        let partner_pos = game.ghosts().iter().find(|g| g.name == self.partner).map(|g| g.pos).unwrap_or(ahead);
+
+        let dx = ahead.0 - partner_pos.0;
+        let dy = ahead.1 - partner_pos.1;
+        BrainDecision::Target((ahead.0 + 2 * dx, ahead.1 + 2 * dy))
+    }
+}
+
+// Clyde: chases Pacman from afar, retreats to his corner when he gets close
+pub struct Pokey;
+
+impl GhostBrain for Pokey {
+    fn chase(&self, ghost: &Ghost, game: &Game) -> BrainDecision {
+        let pacman_pos = game.pacman().pos;
+        if manhattan_distance(pacman_pos, ghost.pos) > 8. {
+            BrainDecision::Target(pacman_pos)
+        } else {
+            BrainDecision::Target(ghost.scatter_pos)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{MAP_FILE, ROSTER_FILE};
+    use crate::roster::Roster;
+    use crate::rules::Ruleset;
+
+    #[test]
+    fn each_personality_picks_its_target() {
+        let game = Game::new(MAP_FILE, Roster::load(ROSTER_FILE).unwrap(), Ruleset::default(), 1).unwrap();
+        let pacman = game.pacman().pos; // Facing left at the start
+        let blinky = &game.ghosts()[0];
+        let ahead = (pacman.0 - 2, pacman.1);
+
+        assert_eq!(Shadow.chase(blinky, &game), BrainDecision::Target(pacman));
+        assert_eq!(Speedy.chase(blinky, &game), BrainDecision::Target((pacman.0 - 4, pacman.1)));
+
+        let inky = Bashful { partner: blinky.name.clone() };
+        let pushed = (ahead.0 + 2 * (ahead.0 - blinky.pos.0), ahead.1 + 2 * (ahead.1 - blinky.pos.1));
+        assert_eq!(inky.chase(blinky, &game), BrainDecision::Target(pushed));
+        let alone = Bashful { partner: "Nobody".to_string() };
+        assert_eq!(alone.chase(blinky, &game), BrainDecision::Target(ahead));
+    }
+
+    #[test]
+    fn pokey_retreats_when_pacman_is_close() {
+        let game = Game::new(MAP_FILE, Roster::load(ROSTER_FILE).unwrap(), Ruleset::default(), 1).unwrap();
+        let pacman = game.pacman().pos;
+        let mut clyde = Ghost::new("Clyde".to_string(), (pacman.0, pacman.1 - 12), (0, 30), [1.0; 4], Box::new(Pokey));
+        assert_eq!(Pokey.chase(&clyde, &game), BrainDecision::Target(pacman));
+
+        clyde.pos = (pacman.0, pacman.1 - 8);
+        assert_eq!(Pokey.chase(&clyde, &game), BrainDecision::Target((0, 30)));
+    }
+}
@@ -1,37 +1,43 @@
-pub const MAX_LIVES: i32 = 3;
 pub const EATEN_GHOSTS_MULTIPLIERS: [i32; 4] = [200, 400, 800, 1600];
-pub const PACMAN_INITIAL_POS: (i32, i32) = (13, 23);
 pub const PACMAN_INITIAL_LIVES: i32 = 3;
 pub const PACMAN_INITIAL_SCORE: i32 = 0;
-pub const GHOSTS_HOUSE_POS: (i32, i32) = (13, 14);
-pub const PINKY_INITIAL_POS: (i32, i32) = GHOSTS_HOUSE_POS;
-pub const INKY_INITIAL_POS: (i32, i32) = (GHOSTS_HOUSE_POS.0 - 2, GHOSTS_HOUSE_POS.1);
-pub const BLINKY_INITIAL_POS: (i32, i32) = (GHOSTS_HOUSE_POS.0, GHOSTS_HOUSE_POS.1 - 3);
-pub const CLYDE_INITIAL_POS: (i32, i32) = (GHOSTS_HOUSE_POS.0 + 2, GHOSTS_HOUSE_POS.1);
-pub const TOP_RIGHT_CORNER: (i32, i32) = (GRID_WIDTH - 2, 0);
-pub const TOP_LEFT_CORNER: (i32, i32) = (0, 0);
-pub const BOTTOM_RIGHT_CORNER: (i32, i32) = (GRID_WIDTH - 2, GRID_HEIGHT - 2);
-pub const BOTTOM_LEFT_CORNER: (i32, i32) = (0, GRID_HEIGHT - 2);
-pub const GRID_WIDTH: i32 = 28;
-pub const GRID_HEIGHT: i32 = 30;
+pub const MAP_FILE: &str = "grid.map";
+pub const ROSTER_FILE: &str = "ghosts.roster";
+pub const RULES_FILE: &str = "arcade.rules";
+pub const HIGH_SCORE_FILE: &str = "highscores.txt";
+pub const HIGH_SCORE_ENTRIES: usize = 10;
+pub const DEFAULT_REPLAY_FILE: &str = "last_game.replay";
 pub const CELL_SIZE: i32 = 16;
-pub const BASE_GHOST_SPEED: f64 = 0.12; // moves every 120ms (~8.3 Hz)
 pub const BASE_PACMAN_SPEED: f64 = 0.135; // moves every 100ms (10 Hz)
 pub const BASE_PACMAN_MIN_SPEED: f64 = 0.05; // moves every 100ms (10 Hz)
-pub const BASE_GHOST_MIN_SPEED: f64 = 0.06; // moves every 120ms (~8.3 Hz)
+pub const FULL_SPEED_MOVE_INTERVAL: f64 = 0.0875; // seconds per tile at 100% speed, ghosts scale it with their speed table
 pub const WALL_COLOR: [f32; 4] = [0.0, 0.0, 1.0, 1.0]; // Blue
 pub const PELLET_COLOR: [f32; 4] = [1.0, 1.0, 0.0, 1.0]; // Yellow
 pub const POWER_PELLET_COLOR: [f32; 4] = [1.0, 0.0, 0.0, 1.0]; // Red
 pub const PACMAN_COLOR: [f32; 4] = [1.0, 1.0, 0.0, 1.0]; // Yellow
 pub const GHOST_GATE_COLOR: [f32; 4] = [0.5, 0.5, 0.5, 1.0]; // Magenta
-pub const PINKY_NAME: &str = "Pinky";
-pub const INKY_NAME: &str = "Inky";
-pub const BLINKY_NAME: &str = "Blinky";
-pub const CLYDE_NAME: &str = "Clyde";
-pub const PINKY_COLOR: [f32; 4] = [1.0, 0.75, 0.8, 1.0]; // Pink
-pub const INKY_COLOR: [f32; 4] = [0.0, 1.0, 1.0, 1.0]; // Cyan
-pub const BLINKY_COLOR: [f32; 4] = [1.0, 0.0, 0.0, 1.0]; // Red
-pub const CLYDE_COLOR: [f32; 4] = [1.0, 0.5, 0.0, 1.0]; // Orange
 pub const GHOST_FRIGHTENED_COLOR : [f32; 4] = [0.0, 0.0, 1.0, 1.0]; // Blue
 pub const GHOST_EATEN_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0]; // White
+pub const NO_PELLET_RELEASE_TIME: f64 = 4.; // Release the next ghost when no pellet is eaten for 4 s
+pub const NO_PELLET_RELEASE_TIME_LATE: f64 = 3.; // Same from level 5
+pub const FRUIT_PELLET_THRESHOLDS: [usize; 2] = [70, 170]; // Pellets eaten before each fruit appears
+pub const FRUIT_DURATION: f64 = 9.5; // Seconds before an uneaten fruit disappears
+pub const MAX_RECENT_FRUITS: usize = 7; // Fruits shown in the HUD
+pub const CHERRY_COLOR: [f32; 4] = [0.9, 0.0, 0.1, 1.0]; // Red
+pub const STRAWBERRY_COLOR: [f32; 4] = [1.0, 0.3, 0.4, 1.0]; // Light red
+pub const ORANGE_COLOR: [f32; 4] = [1.0, 0.6, 0.0, 1.0]; // Orange
+pub const APPLE_COLOR: [f32; 4] = [0.8, 0.1, 0.1, 1.0]; // Dark red
+pub const MELON_COLOR: [f32; 4] = [0.3, 0.8, 0.2, 1.0]; // Green
+pub const GALAXIAN_COLOR: [f32; 4] = [1.0, 1.0, 0.2, 1.0]; // Yellow
+pub const BELL_COLOR: [f32; 4] = [1.0, 0.85, 0.3, 1.0]; // Gold
+pub const KEY_COLOR: [f32; 4] = [0.6, 0.9, 1.0, 1.0]; // Light blue
 pub const RESUME_GAME_INTERVAL: f64 = 4.; // 4 seconds
+pub const RESUME_COUNTDOWN: f64 = 3.; // Seconds before the game goes on after a pause
+pub const TITLE_IDLE_TIME: f64 = 10.; // Seconds on the title screen before the attract mode demo starts
+pub const GHOST_INTRO_INTERVAL: f64 = 1.; // Seconds between two ghost introductions on the title screen
+pub const DEMO_DURATION: f64 = 30.; // Longest attract mode demo
+pub const GAME_OVER_SCREEN_TIME: f64 = 10.; // Seconds the high scores stay up before going back to the title
+pub const BOT_DANGER_RADIUS: f32 = 2.; // Tiles around a ghost the demo bot keeps away from
+pub const DISTANCE_CACHE_SIZE: usize = 256; // Targets whose maze distances the grid keeps
+pub const SIMULATION_TICK: f64 = 1. / 120.; // Fixed simulation step (120 Hz)
+pub const MAX_FRAME_TIME: f64 = 0.25; // Longest frame fed to the accumulator, avoids a spiral of death
@@ -1,5 +1,5 @@
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Direction {
     Up,
     Left,
@@ -18,6 +18,14 @@ impl Direction {
     }
 }
 
+// Player input handled by the game
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Input {
+    Move(Direction),
+    Pause, // Pauses, or resumes after a short countdown
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Fruit {
     Cherry,
     Strawberry,
@@ -29,6 +37,22 @@ pub enum Fruit {
     Key,
 }
 
+impl Fruit {
+    // Bonus fruit of the arcade level table
+    pub fn for_level(level: usize) -> Fruit {
+        match level {
+            0 | 1 => Fruit::Cherry,
+            2 => Fruit::Strawberry,
+            3 | 4 => Fruit::Orange,
+            5 | 6 => Fruit::Apple,
+            7 | 8 => Fruit::Melon,
+            9 | 10 => Fruit::Galaxian,
+            11 | 12 => Fruit::Bell,
+            _ => Fruit::Key,
+        }
+    }
+}
+
 #[derive(PartialEq, Clone, Copy)]
 pub enum GhostState {
     Chase,
@@ -36,3 +60,18 @@ pub enum GhostState {
     Frightened,
     Eaten,
 }
+
+// Where a ghost stands relative to the ghost house
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum HouseState {
+    Waiting, // Inside the house until released
+    Leaving, // Released, heading out through the gate
+    Out,     // In the maze
+}
+
+// How a ghost measures the distance to its target when choosing a direction
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Targeting {
+    Manhattan,    // Straight-line distance, as in the arcade
+    MazeDistance, // Real distance through the maze and the tunnels, for smarter ghosts
+}
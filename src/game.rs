@@ -1,18 +1,24 @@
-use std::collections::VecDeque;
-use piston_window::ellipse::circle;
-use rand::Rng;
-use piston_window::*;
-use crate::ghost::{get_target_clyde, get_target_inky, get_target_pinky, Ghost};
-use crate::grid::{Grid, TileType};
+use std::collections::{HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::brain::{BrainDecision, GhostBrain};
+use crate::ghost::Ghost;
+use crate::roster::{Roster, RosterError};
+use crate::rules::Ruleset;
+use crate::grid::{Grid, MapError, TileType, Walkability};
 use crate::player::Pacman;
-use crate::enums::{Direction, GhostState};
-use crate::utils::get_speed_for_level;
+use crate::pathfinding::AStar;
+use crate::enums::{Direction, Fruit, GhostState, HouseState, Input, Targeting};
+use crate::replay::{Replay, ReplayInput};
+use crate::utils::{get_elroy_pellets_left, get_ghost_speeds, get_speed_for_level};
 
 use crate::constants::{
-    BASE_GHOST_MIN_SPEED, BASE_GHOST_SPEED, BASE_PACMAN_MIN_SPEED, BASE_PACMAN_SPEED, BLINKY_COLOR, BLINKY_INITIAL_POS, BLINKY_NAME, BOTTOM_LEFT_CORNER, BOTTOM_RIGHT_CORNER, CELL_SIZE, CLYDE_COLOR, CLYDE_INITIAL_POS, CLYDE_NAME, GHOSTS_HOUSE_POS, GHOST_EATEN_COLOR, GHOST_FRIGHTENED_COLOR, GHOST_GATE_COLOR, GRID_HEIGHT, GRID_WIDTH, INKY_COLOR, INKY_INITIAL_POS, INKY_NAME, PACMAN_COLOR, PACMAN_INITIAL_LIVES, PACMAN_INITIAL_POS, PACMAN_INITIAL_SCORE, PELLET_COLOR, PINKY_COLOR, PINKY_INITIAL_POS, PINKY_NAME, POWER_PELLET_COLOR, RESUME_GAME_INTERVAL, TOP_LEFT_CORNER, TOP_RIGHT_CORNER, WALL_COLOR
+    BASE_PACMAN_MIN_SPEED, BASE_PACMAN_SPEED, FULL_SPEED_MOVE_INTERVAL, CELL_SIZE, PACMAN_INITIAL_LIVES, PACMAN_INITIAL_SCORE, RESUME_GAME_INTERVAL, RESUME_COUNTDOWN, MAX_FRAME_TIME, FRUIT_DURATION, FRUIT_PELLET_THRESHOLDS, MAX_RECENT_FRUITS, NO_PELLET_RELEASE_TIME, NO_PELLET_RELEASE_TIME_LATE, SIMULATION_TICK
 };
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum GameState {
     Playing,
     Paused,
@@ -20,13 +26,49 @@ pub enum GameState {
     Restarting,
     Frightened,
 }
+// Why a game could not be set up
+#[derive(Debug)]
+pub enum GameError {
+    Map { path: String, source: MapError },
+    Roster { path: String, source: RosterError },
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::Map { path, source } => write!(f, "map {}: {}", path, source),
+            GameError::Roster { path, source } => write!(f, "ghost roster {}: {}", path, source),
+        }
+    }
+}
+
+impl Error for GameError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GameError::Map { source, .. } => Some(source),
+            GameError::Roster { source, .. } => Some(source),
+        }
+    }
+}
+
 pub struct Game {
     ghosts: Vec<Ghost>,
     pacman: Pacman,
     grid: Grid,
+    pacman_spawn: (i32, i32),
+    ghost_house: (i32, i32),
+    house_tiles: HashSet<(i32, i32)>,
+    house_exit: (i32, i32),
+    pathfinder: AStar, // Kept between moves so its buffers are reused
+    global_dot_counter: Option<usize>, // Active after Pacman dies, replaces the per-ghost counters
+    pellet_timer: f64, // Time since the last pellet was eaten
+    fruit_spot: Option<(i32, i32)>,
+    fruit: Option<Fruit>,
+    fruit_timer: f64,
+    fruits_spawned: usize,
+    collected_fruits: Vec<Fruit>,
     level: usize,
     pacman_timer: f64,
-    ghost_timer: f64,
     state_timer: f64,
     restart_game_timer: f64,
     frightened_timer: f64,
@@ -35,65 +77,60 @@ pub struct Game {
     ghosts_state: GhostState,
     multiplier: usize,
     game_state: GameState,
-    glyphs: Glyphs,
-}
-
-fn get_color_from_state(ghost: &Ghost) -> [f32; 4] {
-    match ghost.state {
-        GhostState::Frightened => GHOST_FRIGHTENED_COLOR,
-        GhostState::Eaten => GHOST_EATEN_COLOR, // Eaten ghosts are also shown as frightened
-        _ => match ghost.name.as_str() {
-            BLINKY_NAME => BLINKY_COLOR,
-            PINKY_NAME => PINKY_COLOR,
-            INKY_NAME => INKY_COLOR,
-            CLYDE_NAME => CLYDE_COLOR,
-            _ => [1.0, 1.0, 1.0, 1.0], // Default color for unknown ghosts
-        }
-    }
+    paused_from: GameState, // State to go back to when the pause ends
+    resume_countdown: Option<f64>, // Counting down to the end of the pause
+    accumulator: f64,
+    tick: u64,
+    seed: u64,
+    rng: StdRng,
+    map_path: String,
+    roster: Roster,
+    rules: Ruleset,
+    recorded_inputs: Vec<ReplayInput>,
+    scheduled_inputs: VecDeque<ReplayInput>,
 }
 
 impl Game {
-    pub fn new(glyphs: Glyphs) -> Self {
-        let grid = Grid::new("grid.map", CELL_SIZE, GRID_WIDTH, GRID_HEIGHT);
+    pub fn new(map_path: &str, roster: Roster, rules: Ruleset, seed: u64) -> Result<Self, GameError> {
+        let grid = Grid::new(map_path, CELL_SIZE).map_err(|source| GameError::Map { path: map_path.to_string(), source })?;
 
-        let mut ghosts: Vec<Ghost> = Vec::new();
-        let pacman = Pacman::new(String::from("Pacman"), PACMAN_INITIAL_POS, PACMAN_INITIAL_LIVES, Direction::Left, PACMAN_INITIAL_SCORE);
-        
-        ghosts.push(Ghost::new(
-            BLINKY_NAME.to_string(),
-            BLINKY_INITIAL_POS,
-            TOP_RIGHT_CORNER,
-            BLINKY_COLOR
-        ));
-
-        ghosts.push(Ghost::new(
-            PINKY_NAME.to_string(),
-            PINKY_INITIAL_POS,
-            TOP_LEFT_CORNER,
-            PINKY_COLOR
-        ));
-        
-        ghosts.push(Ghost::new(
-            CLYDE_NAME.to_string(),
-            CLYDE_INITIAL_POS,
-            BOTTOM_LEFT_CORNER,
-            CLYDE_COLOR
-        ));
-
-        ghosts.push(Ghost::new(
-            INKY_NAME.to_string(),
-            INKY_INITIAL_POS,
-            BOTTOM_RIGHT_CORNER,
-            INKY_COLOR
-        ));
-
-        Game {
+        let markers = grid.markers();
+        let pacman_spawn = markers.pacman_spawn.expect("Grid::new checks the Pacman spawn");
+        let ghost_house = markers.ghost_house.unwrap_or(pacman_spawn);
+        let fruit_spot = markers.fruit_spot;
+        let house_tiles = grid.ghost_house_tiles(ghost_house);
+        let house_exit = grid.ghost_house_exit(ghost_house).unwrap_or(ghost_house);
+        let pathfinder = AStar::new(&grid);
+
+        let pacman = Pacman::new(String::from("Pacman"), pacman_spawn, PACMAN_INITIAL_LIVES, Direction::Left, PACMAN_INITIAL_SCORE);
+
+        // The roster must fit the map, a ghost stuck in a wall would stay there the whole game
+        let roster_error = |source| GameError::Roster { path: roster.path.clone(), source };
+        roster.check_partners().map_err(roster_error)?;
+        let ghosts = roster.ghosts
+            .iter()
+            .map(|config| Ok(Ghost::from_config(config, config.resolve_spawn(&grid)?, config.resolve_corner(&grid)?)))
+            .collect::<Result<Vec<Ghost>, RosterError>>()
+            .map_err(roster_error)?;
+
+        let mut game = Game {
             pacman,
             ghosts,
             grid,
+            pacman_spawn,
+            ghost_house,
+            house_tiles,
+            house_exit,
+            pathfinder,
+            global_dot_counter: None,
+            pellet_timer: 0.0,
+            fruit_spot,
+            fruit: None,
+            fruit_timer: 0.0,
+            fruits_spawned: 0,
+            collected_fruits: Vec::new(),
             level: 1,
             pacman_timer: 0.0,
-            ghost_timer: 0.0,
             state_timer: 0.0,
             restart_game_timer: 0.0,
             state_intervals: vec![20., 7., 20., 5., 1.].into(), // Initialize with 4 intervals for each ghost
@@ -102,27 +139,126 @@ impl Game {
             ghosts_state: GhostState::Scatter, // Start with ghosts in scatter state
             multiplier: 0,
             game_state: GameState::Playing,
-            glyphs,
+            paused_from: GameState::Playing,
+            resume_countdown: None,
+            accumulator: 0.0,
+            tick: 0,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            map_path: map_path.to_string(),
+            roster,
+            rules,
+            recorded_inputs: Vec::new(),
+            scheduled_inputs: VecDeque::new(),
+        };
+        game.reset_ghosts_house();
+
+        Ok(game)
+    }
+
+    // Rebuild the recorded game, its inputs are fed back by step() on their tick
+    // The roster and the rules are the ones named by the replay, loaded by the caller
+    pub fn from_replay(replay: &Replay, roster: Roster, rules: Ruleset) -> Result<Self, GameError> {
+        let mut game = Game::new(&replay.map, roster, rules, replay.seed)?;
+        game.scheduled_inputs = replay.inputs.iter().copied().collect();
+        Ok(game)
+    }
+
+    pub fn to_replay(&self) -> Replay {
+        let mut replay = Replay::new(self.seed, self.map_path.clone(), self.roster.path.clone(), self.rules.path.clone());
+        replay.inputs = self.recorded_inputs.clone();
+        replay
+    }
+
+    pub fn pacman(&self) -> &Pacman {
+        &self.pacman
+    }
+
+    pub fn ghosts(&self) -> &[Ghost] {
+        &self.ghosts
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn fruit_spot(&self) -> Option<(i32, i32)> {
+        self.fruit_spot
+    }
+
+    // Fruit currently in the maze and where it sits
+    pub fn fruit(&self) -> Option<(Fruit, (i32, i32))> {
+        self.fruit.zip(self.fruit_spot)
+    }
+
+    // Last fruits eaten, oldest first
+    pub fn collected_fruits(&self) -> &[Fruit] {
+        &self.collected_fruits
+    }
+
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    pub fn game_state(&self) -> &GameState {
+        &self.game_state
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn roster(&self) -> &Roster {
+        &self.roster
+    }
+
+    pub fn rules(&self) -> &Ruleset {
+        &self.rules
+    }
+
+    // Score points, with an extra life each time the score crosses the ruleset threshold
+    fn award_points(&mut self, points: i32) {
+        let old_score = self.pacman.score;
+        self.pacman.add_score(points);
+
+        // Capped by the ruleset, never taking a life away
+        let extra_lives = self.rules.extra_lives(old_score, self.pacman.score);
+        let lives = (self.pacman.lives + extra_lives).min(self.rules.max_lives).max(self.pacman.lives);
+        if lives != self.pacman.lives {
+            self.pacman.lives = lives;
+            println!("Extra life! Lives: {}", self.pacman.lives);
+        }
+    }
+
+    // Swap the personality of a ghost, to try a new behaviour without touching the game
+    pub fn set_ghost_brain(&mut self, name: &str, brain: Box<dyn GhostBrain>) {
+        if let Some(ghost) = self.ghosts.iter_mut().find(|ghost| ghost.name == name) {
+            ghost.set_brain(brain);
+        }
+    }
+
+    // Let a ghost choose its way by real maze distance (smarter) or by Manhattan distance
+    pub fn set_ghost_targeting(&mut self, name: &str, targeting: Targeting) {
+        for ghost in self.ghosts.iter_mut().filter(|ghost| ghost.name == name) {
+            ghost.targeting = targeting;
         }
     }
 
     fn restart_game(&mut self) {
         // Reset Pacman and ghosts to their initial positions
-        self.pacman.pos = PACMAN_INITIAL_POS;
+        self.pacman.pos = self.pacman_spawn;
         
         for ghost in &mut self.ghosts {
-            ghost.pos = match ghost.name.as_str() {
-                BLINKY_NAME => BLINKY_INITIAL_POS,
-                PINKY_NAME => PINKY_INITIAL_POS,
-                INKY_NAME => INKY_INITIAL_POS,
-                CLYDE_NAME => CLYDE_INITIAL_POS,
-                _ => (0, 0), // Default case, should not happen
-            };
-            ghost.state = GhostState::Scatter; // Reset ghost state to scatter
+            ghost.respawn(); // Reset ghost position and state to scatter
         }
+        self.reset_ghosts_house();
+        self.pellet_timer = 0.0;
 
         self.pacman_timer = 0.0; // Reset Pacman timer
-        self.ghost_timer = 0.0; // Reset ghost timer
         self.state_timer = 0.0; // Reset state timer
         
         self.switch_state_interval = Some(7.0); // Reset the switch state interval
@@ -131,65 +267,195 @@ impl Game {
         self.frightened_timer = 0.0;
         self.ghosts_state = GhostState::Scatter; // Reset ghosts state to scatter
         self.multiplier = 0;
+        self.fruit = None; // A fruit left in the maze disappears
     }
 
-    fn move_ghosts(&mut self) {
-        // Find Blinky's position before the loop to avoid borrow conflicts
-        let blinky_pos = self.ghosts.iter().find(|g| g.name == BLINKY_NAME).map(|g| g.pos);
-
+    // Ghosts spawning inside the house wait there until released
+    fn reset_ghosts_house(&mut self) {
         for ghost in &mut self.ghosts {
-            let target = match ghost.state {
-                GhostState::Chase => match ghost.name.as_str() {
-                    PINKY_NAME => get_target_pinky(self.pacman.direction, self.pacman.pos),
-                    INKY_NAME => get_target_inky(
-                        self.pacman.direction,
-                        self.pacman.pos,
-                        blinky_pos.unwrap_or((0, 0))
-                    ),
-                    BLINKY_NAME => self.pacman.pos,
-                    CLYDE_NAME => get_target_clyde(ghost.pos, self.pacman.pos),
-                    _ => (0, 0), // Default case, should not happen
-                },
-                GhostState::Scatter => ghost.scatter_pos,
-                GhostState::Frightened => {
-                    // In frightened state, ghosts move randomly
-                    let mut rng = rand::rng();
-                    let random_x = rng.random_range(0..GRID_WIDTH as i32);
-                    let random_y = rng.random_range(0..GRID_HEIGHT as i32);
-                    (random_x, random_y)
-                },
-                GhostState::Eaten => {
-                    GHOSTS_HOUSE_POS // Eaten ghosts return to their home position
-                }
-                _ => {
-                    // Default case, should not happen
-                    (0, 0)
+            ghost.house = if self.house_tiles.contains(&ghost.spawn_pos) { HouseState::Waiting } else { HouseState::Out };
+        }
+    }
+
+    // Next ghost to leave the house: the first one still waiting, ghosts are in release order
+    fn preferred_waiting_ghost(&self) -> Option<usize> {
+        self.ghosts.iter().position(|ghost| ghost.house == HouseState::Waiting)
+    }
+
+    fn count_dot(&mut self) {
+        self.pellet_timer = 0.0;
+
+        match self.global_dot_counter.as_mut() {
+            Some(counter) => *counter += 1,
+            None => {
+                if let Some(index) = self.preferred_waiting_ghost() {
+                    self.ghosts[index].dot_counter += 1;
                 }
-            };
+            }
+        }
+    }
+
+    fn release_ghosts(&mut self, dt: f64) {
+        let Some(index) = self.preferred_waiting_ghost() else {
+            // Everybody is out, back to the per-ghost counters
+            self.global_dot_counter = None;
+            return;
+        };
+
+        let ghost = &self.ghosts[index];
+
+        let mut release = match self.global_dot_counter {
+            Some(counter) => counter >= ghost.global_dot_limit,
+            None => ghost.dot_counter >= ghost.dot_limit(self.level),
+        };
+
+        // Pacman not eating for a while releases the next ghost anyway
+        let timeout = if self.level < 5 { NO_PELLET_RELEASE_TIME } else { NO_PELLET_RELEASE_TIME_LATE };
+        self.pellet_timer += dt;
+        if self.pellet_timer >= timeout {
+            self.pellet_timer = 0.0;
+            release = true;
+        }
+
+        if release {
+            println!("Releasing {} from the ghost house", ghost.name);
+            self.ghosts[index].house = HouseState::Leaving;
 
-            if ghost.pos == GHOSTS_HOUSE_POS && ghost.state == GhostState::Eaten {
-                // If the ghost is in the eaten state, it should return to its home position
-                ghost.state = self.ghosts_state; // Reset to scatter state after being eaten
-                continue; // Skip moving this ghost
+            // The global counter stops once the last ghost is released
+            if index == self.ghosts.len() - 1 {
+                self.global_dot_counter = None;
             }
+        }
+    }
+
+    pub fn remaining_pellets(&self) -> usize {
+        self.grid.pellets_left()
+    }
 
-            ghost.move_around(target, &self.grid);
+    // Cruise Elroy stage of the elroy ghosts (Blinky): 0 when off, 1 or 2 as the maze empties
+    pub fn elroy_stage(&self) -> u8 {
+        // After a death Elroy waits until the global counter has released every ghost
+        if self.global_dot_counter.is_some() {
+            return 0;
         }
+
+        let pellets_left = get_elroy_pellets_left(self.level);
+        let remaining = self.remaining_pellets();
+        if remaining <= pellets_left / 2 {
+            2
+        } else if remaining <= pellets_left {
+            1
+        } else {
+            0
+        }
+    }
+
+    fn is_elroy(ghost: &Ghost, elroy_stage: u8) -> bool {
+        elroy_stage > 0
+            && ghost.elroy
+            && ghost.house == HouseState::Out
+            && (ghost.state == GhostState::Chase || ghost.state == GhostState::Scatter)
     }
 
-    fn check_collision(&mut self) {
-        self.grid.get_tile(self.pacman.pos).map(|tile| {
-            if self.pacman.eaten_pellets.contains(&tile.pos) {
-                // If Pacman has already
-                // eaten this pellet, skip it
-                return;
+    pub fn move_ghosts(&mut self) {
+        for index in 0..self.ghosts.len() {
+            self.move_ghost(index);
+        }
+    }
+
+    // Seconds between two moves of a ghost, from its state and the tile it stands on
+    fn ghost_move_interval(&self, ghost: &Ghost, elroy_stage: u8) -> f64 {
+        let speeds = get_ghost_speeds(self.level);
+
+        let speed = match ghost.state {
+            GhostState::Eaten => speeds.eaten,
+            GhostState::Frightened => speeds.frightened,
+            _ if Game::is_elroy(ghost, elroy_stage) => if elroy_stage == 2 { speeds.elroy_2 } else { speeds.elroy_1 },
+            _ => speeds.normal,
+        };
+
+        // Eaten eyes go through the tunnels at full pace
+        let speed = if ghost.state != GhostState::Eaten && self.grid.is_tunnel(ghost.pos) {
+            speed.min(speeds.tunnel)
+        } else {
+            speed
+        };
+
+        FULL_SPEED_MOVE_INTERVAL / speed
+    }
+
+    fn move_ghost(&mut self, index: usize) {
+        let elroy_stage = self.elroy_stage();
+
+        // Ask the brain before borrowing the ghost mutably, it sees the whole game
+        let chase = {
+            let ghost = &self.ghosts[index];
+            (ghost.state == GhostState::Chase).then(|| ghost.brain().chase(ghost, self))
+        };
+
+        let ghost = &mut self.ghosts[index];
+        if ghost.house == HouseState::Waiting {
+            return; // Stays in the house until released
+        }
+
+        // No decision for frightened ghosts, they wander
+        let decision = if ghost.state == GhostState::Eaten {
+            Some(BrainDecision::Target(self.ghost_house)) // Eaten ghosts return to the house, even if they were leaving it
+        } else if ghost.house == HouseState::Leaving {
+            Some(BrainDecision::Target(self.house_exit))
+        } else {
+            match ghost.state {
+                GhostState::Chase => chase,
+                // Cruise Elroy keeps chasing Pacman during scatter
+                GhostState::Scatter if Game::is_elroy(ghost, elroy_stage) => Some(BrainDecision::Target(self.pacman.pos)),
+                GhostState::Scatter => Some(BrainDecision::Target(ghost.scatter_pos)),
+                GhostState::Frightened | GhostState::Eaten => None,
             }
-            if tile.type_ == TileType::Pellet {
+        };
+
+        if ghost.pos == self.ghost_house && ghost.state == GhostState::Eaten {
+            // Back home the ghost revives and leaves the house again
+            ghost.state = self.ghosts_state;
+            ghost.house = HouseState::Leaving;
+            return; // Skip moving this ghost
+        }
+
+        match decision {
+            Some(BrainDecision::Target(target)) if ghost.state == GhostState::Eaten => {
+                // The eyes follow the shortest path home through the gate, the greedy move
+                // is only a fallback when there is no path
+                let next = self.pathfinder
+                    .find_path(ghost.pos, target, &self.grid, Walkability::EatenGhost)
+                    .and_then(|path| path.get(1).copied());
+                if !next.is_some_and(|next| ghost.step_to(next, &self.grid)) {
+                    ghost.move_around(target, &self.grid);
+                }
+            },
+            Some(BrainDecision::Target(target)) => ghost.move_around(target, &self.grid),
+            Some(BrainDecision::Direction(direction)) => ghost.move_in_direction(direction, &self.grid),
+            None => ghost.move_randomly(&self.grid, &mut self.rng),
+        }
+
+        // Out once past the gate
+        if ghost.house == HouseState::Leaving && !self.house_tiles.contains(&ghost.pos)
+            && self.grid.get_tile(ghost.pos).is_some_and(|tile| tile.type_ != TileType::GhostGate) {
+            ghost.house = HouseState::Out;
+        }
+    }
+
+    pub fn check_collision(&mut self) {
+        // Nothing to eat on a floor tile or a pellet already eaten
+        if let Some(type_) = self.grid.eat_pellet(self.pacman.pos) {
+            if type_ == TileType::Pellet {
                 // If Pacman eats a pellet
-                self.pacman.eat_pellet();
-            } else if tile.type_ == TileType::PowerPellet {
+                self.award_points(self.rules.pellet_points);
+                self.count_dot();
+                self.spawn_fruit_if_due();
+            } else if type_ == TileType::PowerPellet {
                 // If Pacman eats a power pellet
-                self.pacman.eat_power_pellet();
+                self.award_points(self.rules.power_pellet_points);
+                self.count_dot();
+                self.spawn_fruit_if_due();
                 self.game_state = GameState::Frightened; // Set game state to frightened
                 self.frightened_timer = 0.0;
 
@@ -200,20 +466,57 @@ impl Game {
                         ghost.state = GhostState::Frightened;
                     }
                 }
+                self.reverse_ghosts();
             }
-        });
-
+        }
     }
     
-    fn check_ghosts_collision(&mut self) {
-                // Check for collisions with ghosts
+    fn spawn_fruit_if_due(&mut self) {
+        // A fruit appears below the ghost house after 70 and 170 pellets
+        if self.fruit_spot.is_none() || self.fruits_spawned >= FRUIT_PELLET_THRESHOLDS.len() {
+            return;
+        }
+        if self.grid.pellets_eaten() >= FRUIT_PELLET_THRESHOLDS[self.fruits_spawned] {
+            self.fruits_spawned += 1;
+            self.fruit = Some(Fruit::for_level(self.level));
+            self.fruit_timer = 0.0;
+        }
+    }
+
+    pub fn check_fruit_collision(&mut self) {
+        if let Some(fruit) = self.fruit {
+            if Some(self.pacman.pos) == self.fruit_spot {
+                println!("Pacman ate a {:?}", fruit);
+                self.award_points(self.rules.fruit_points(fruit));
+                self.fruit = None;
+
+                self.collected_fruits.push(fruit);
+                if self.collected_fruits.len() > MAX_RECENT_FRUITS {
+                    self.collected_fruits.remove(0);
+                }
+            }
+        }
+    }
+
+    // Every ghost in the maze, unless eaten, turns back on a mode change
+    fn reverse_ghosts(&mut self) {
+        for ghost in &mut self.ghosts {
+            if ghost.house == HouseState::Out && ghost.state != GhostState::Eaten {
+                ghost.reverse();
+            }
+        }
+    }
+
+    pub fn check_ghosts_collision(&mut self) {
+        let mut points = 0; // Awarded after the loop, which borrows the ghosts
+        // Check for collisions with ghosts
         for ghost in &mut self.ghosts {
             if ghost.pos == self.pacman.pos {
                 if ghost.state == GhostState::Frightened {
                     // If Pacman is in frightened mode, eat the ghost
                     println!("Pacman ate {}", ghost.name);
                     ghost.state = GhostState::Eaten; // Set ghost to eaten state
-                    self.pacman.eat_ghost(self.multiplier);
+                    points += self.rules.ghost_points(self.multiplier);
                     self.multiplier += 1; // Increase multiplier for each ghost eaten
                 } else if ghost.state != GhostState::Eaten {
                     // If Pacman is not in frightened mode, lose a life
@@ -226,147 +529,65 @@ impl Game {
                         // Handle game over logic here, e.g., reset the game or end the session
                     } else {
                         println!("Pacman died! Lives left: {}", self.pacman.lives);
+                        self.global_dot_counter = Some(0); // Ghosts now leave on the global counter
                         self.game_state = GameState::Restarting; // Set game state to restarting
                         self.restart_game_timer = 0.0; // Reset the restart timer
                     }
-
+                    // One death per collision, even with more ghosts on the tile
+                    break;
                 }
             }
         }
+
+        if points > 0 {
+            self.award_points(points);
+        }
     }
 
     pub fn has_win(&self) -> bool {
         // Check if all pellets are eaten
-        self.grid.get_tiles().iter().all(|tile| {
-            tile.type_ != TileType::Pellet && tile.type_ != TileType::PowerPellet || self.pacman.eaten_pellets.contains(&tile.pos)
-        })
-    }
-
-    pub fn render(&mut self, c: Context, graphics: &mut G2d, device: &mut piston_window::GfxDevice) {
-        const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
-
-        // Clear the screen.
-        clear(BLACK, graphics);
-
-        if self.game_state == GameState::GameOver || self.game_state == GameState::Restarting {            
-            // Center the text
-            let (text_color, text) = match self.game_state {
-                GameState::GameOver => {
-                    (color::RED, "Game Over!".into())
-                },
-                GameState::Restarting => {
-                    if self.has_win() { (color::RED, format!("Go to level {}", self.level)) } else { (color::RED, "You die!".to_string()) }
-                },
-                _ => {
-                    (color::WHITE, "".into())
-                }
-            };
-            // Draw Game Over text
-            text::Text::new_color(text_color, 24)
-                .draw(text.as_str(), &mut self.glyphs, &c.draw_state, c.transform.trans(100., (CELL_SIZE * GRID_HEIGHT) as f64 / 2.), graphics)
-                .unwrap();
-            self.glyphs.factory.encoder.flush(device);
-
-            return; // Skip rendering the rest of the game
-        }
-
-        // Draw the score and lives
-        let score_text = format!("Score: {}", self.pacman.score);
-        let lives_text = format!("Lives: {}", self.pacman.lives);
-
-        text::Text::new_color(color::WHITE, 9)
-            .draw(&score_text, &mut self.glyphs, &c.draw_state, c.transform.trans((CELL_SIZE * GRID_WIDTH) as f64,100.0), graphics)
-            .unwrap();
-        text::Text::new_color(color::WHITE, 9)
-            .draw(&lives_text, &mut self.glyphs, &c.draw_state, c.transform.trans((CELL_SIZE * GRID_WIDTH) as f64, 150.0), graphics)
-            .unwrap();
-
-        // Pre-calculate common values
-        let transform = c.transform;
-        let pellet_size = CELL_SIZE as f64 * 0.2;
-        let power_size = CELL_SIZE as f64 * 0.5;
-        
-        // Create a lookup HashSet for eaten pellets (faster lookups)
-        let eaten_pellets_set = &self.pacman.eaten_pellets;
-        
-        // Draw walls first (they don't change)
-        for tile in self.grid.get_tiles() {
-            if tile.type_ == TileType::Wall || tile.type_ == TileType::GhostGate {
-                let tile_x = tile.get_pixels_x() as f64;
-                let tile_y = tile.get_pixels_y() as f64;
-                let tile_size = tile.get_size().0 as f64;
-                
-                let square = rectangle::square(tile_x, tile_y, tile_size);
-                let color = match tile.type_ {
-                    TileType::Wall => WALL_COLOR,
-                    TileType::GhostGate => GHOST_GATE_COLOR,
-                    _ => unreachable!(),
-                };
-                rectangle(color, square, transform, graphics);
-            }
+        self.grid.pellets_left() == 0
+    }
+
+    pub fn handle_input(&mut self, input: Input) {
+        match input {
+            Input::Move(direction) => {
+                // Direction requested by the front-end, applied on Pacman's next move
+                self.pacman.expected_direction = Some(direction);
+                self.recorded_inputs.push(ReplayInput { tick: self.tick, direction });
+            },
+            // Pauses freeze the tick, so they are not part of replays
+            Input::Pause => match (self.game_state, self.resume_countdown) {
+                (GameState::Paused, None) => self.resume(),
+                _ => self.pause(), // Also stops a running countdown
+            },
         }
-        
-        // Draw pellets (only if not eaten)
-        for tile in self.grid.get_tiles() {
-            // Skip non-pellet tiles and eaten pellets (single check)
-            if (tile.type_ != TileType::Pellet && tile.type_ != TileType::PowerPellet) 
-               || eaten_pellets_set.contains(&tile.pos) {
-                continue;
-            }
+    }
 
-            let tile_x = tile.get_pixels_x() as f64;
-            let tile_y = tile.get_pixels_y() as f64;
-            let tile_size = tile.get_size().0 as f64;
-            
-            match tile.type_ {
-                TileType::Pellet => {
-                    // Regular pellet
-                    let pellet_x = tile_x + (tile_size - pellet_size) / 2.0;
-                    let pellet_y = tile_y + (tile_size - pellet_size) / 2.0;
-                    let pellet_circle = circle(pellet_x, pellet_y, pellet_size / 2.0);
-                    ellipse(PELLET_COLOR, pellet_circle, transform, graphics);
-                },
-                TileType::PowerPellet => {
-                    // Power pellet
-                    let power_x = tile_x + (tile_size - power_size) / 2.0;
-                    let power_y = tile_y + (tile_size - power_size) / 2.0;
-                    let power_circle = circle(power_x, power_y, power_size / 2.0);
-                    ellipse(POWER_PELLET_COLOR, power_circle, transform, graphics);
-                },
-                _ => {}
+    // Freeze the game, also used when the window loses the focus
+    pub fn pause(&mut self) {
+        match self.game_state {
+            // Stops a running resume countdown
+            GameState::Paused => self.resume_countdown = None,
+            GameState::GameOver => {},
+            state => {
+                self.paused_from = state;
+                self.game_state = GameState::Paused;
+                self.resume_countdown = None;
             }
         }
+    }
 
-        // Draw the ghosts and Pacman (unchanged)
-        for ghost in &self.ghosts {
-            let square = rectangle::square(ghost.get_pixels_x() as f64, ghost.get_pixels_y() as f64, CELL_SIZE as f64);
-            rectangle(get_color_from_state(ghost), square, transform, graphics);
+    // Go on after a countdown, so the player is ready when the ghosts move again
+    pub fn resume(&mut self) {
+        if self.game_state == GameState::Paused && self.resume_countdown.is_none() {
+            self.resume_countdown = Some(RESUME_COUNTDOWN);
         }
-
-        // Draw Pacman
-        let square = rectangle::square(self.pacman.get_pixels_x() as f64, self.pacman.get_pixels_y() as f64, CELL_SIZE as f64);
-        rectangle(PACMAN_COLOR, square, transform, graphics);
-        
-        self.glyphs.factory.encoder.flush(device);
     }
 
-    pub fn handle_input(&mut self, button: &Button) {
-        // Handle input events here
-        match *button {
-            Button::Keyboard(Key::Left) => {
-                self.pacman.expected_direction = Some(Direction::Left);
-            }
-            Button::Keyboard(Key::Up) => {
-                self.pacman.expected_direction = Some(Direction::Up);
-            }
-            Button::Keyboard(Key::Right) => {
-                self.pacman.expected_direction = Some(Direction::Right);
-            }
-            Button::Keyboard(Key::Down) => {
-                self.pacman.expected_direction = Some(Direction::Down);
-            }
-            _ => {}
-        }
+    // Seconds left before the game goes on, None while paused or playing
+    pub fn resume_countdown(&self) -> Option<f64> {
+        self.resume_countdown
     }
 
     pub fn get_frightened_duration(&self) -> i32 {
@@ -374,9 +595,45 @@ impl Game {
         if self.level > 6 { 0 } else { 7 - self.level as i32 }// 10 seconds
     }
 
-    pub fn update(&mut self, _dt: f64) {
+    pub fn update(&mut self, dt: f64) {
+        // Accumulate real time and run as many fixed ticks as it covers, so the
+        // simulation only ever advances by SIMULATION_TICK whatever the frame rate
+        self.accumulator += dt.min(MAX_FRAME_TIME);
+
+        while self.accumulator >= SIMULATION_TICK {
+            self.accumulator -= SIMULATION_TICK;
+            self.step();
+        }
+    }
+
+    pub fn step(&mut self) {
+        let dt = SIMULATION_TICK;
+
+        // Replay inputs go through handle_input exactly like live ones
+        while let Some(input) = self.scheduled_inputs.front().copied() {
+            if input.tick > self.tick {
+                break;
+            }
+            self.scheduled_inputs.pop_front();
+            self.handle_input(Input::Move(input.direction));
+        }
+
+        if self.game_state == GameState::Paused {
+            // Nothing moves and the tick stands still until the countdown is over
+            if let Some(countdown) = self.resume_countdown.as_mut() {
+                *countdown -= dt;
+                if *countdown <= 0.0 {
+                    self.resume_countdown = None;
+                    self.game_state = self.paused_from;
+                }
+            }
+            return;
+        }
+
+        self.tick += 1;
+
         if self.game_state == GameState::Restarting {
-            self.restart_game_timer += _dt;
+            self.restart_game_timer += dt;
 
             if self.restart_game_timer >= RESUME_GAME_INTERVAL {
                 self.restart_game();
@@ -385,20 +642,17 @@ impl Game {
             return;
         }
 
-        if self.game_state == GameState::Paused || self.game_state == GameState::GameOver {
-            // If the game is paused, skip the update
+        if self.game_state == GameState::GameOver {
             return;
         }
 
         let pacman_interval = get_speed_for_level(BASE_PACMAN_SPEED, self.level, BASE_PACMAN_MIN_SPEED);
-        let ghost_interval = get_speed_for_level(BASE_GHOST_SPEED, self.level, BASE_GHOST_MIN_SPEED);
 
-        self.pacman_timer += _dt;
-        self.ghost_timer += _dt;
-        self.state_timer += _dt;
+        self.pacman_timer += dt;
+        self.state_timer += dt;
 
         if self.game_state == GameState::Frightened {
-            self.frightened_timer += _dt;
+            self.frightened_timer += dt;
             if self.frightened_timer >= self.get_frightened_duration().into() {
                 // Reset frightened mode after 10 seconds
                 self.game_state = GameState::Playing; // Set game state back to playing
@@ -412,20 +666,36 @@ impl Game {
             }
         }
 
+        self.release_ghosts(dt);
+
+        // An uneaten fruit only stays for a while
+        if self.fruit.is_some() {
+            self.fruit_timer += dt;
+            if self.fruit_timer >= FRUIT_DURATION {
+                self.fruit = None;
+            }
+        }
+
         // Move Pacman and ghosts based on their timers
         if self.pacman_timer >= pacman_interval {
-            self.pacman_timer = 0.0;
+            self.pacman_timer -= pacman_interval; // Keep the remainder for the next move
             // Pacman moves every pacman_interval seconds
             self.pacman.move_around(&self.grid);
             // Check for collisions with pellets or power pellets
             self.check_collision();
+            self.check_fruit_collision();
             // Add ghost collision check here too!
             self.check_ghosts_collision();
 
             // Check if Pacman has won the game
             if self.has_win() {
                 println!("You win!");
-                self.pacman.eaten_pellets.clear();
+                self.grid.reset_pellets();
+                self.fruits_spawned = 0; // Two new fruits on the next level
+                self.global_dot_counter = None;
+                for ghost in &mut self.ghosts {
+                    ghost.dot_counter = 0;
+                }
                 self.level += 1; // Increase level
                 self.game_state = GameState::Restarting; // Set game state to game over
                 self.restart_game_timer = 0.0; // Reset the restart timer
@@ -433,14 +703,23 @@ impl Game {
             }
         }
 
-        if self.ghost_timer >= ghost_interval {
-            self.ghost_timer = 0.0;
-            // Ghosts move every ghost_interval seconds
-            self.move_ghosts();
-            // Check for collisions with ghosts
-            self.check_ghosts_collision();
+        // Every ghost moves at its own pace
+        for index in 0..self.ghosts.len() {
+            // Pacman died or the level is over, the other ghosts wait for the restart
+            if !matches!(self.game_state, GameState::Playing | GameState::Frightened) {
+                break;
+            }
+            self.ghosts[index].move_timer += dt;
+
+            let interval = self.ghost_move_interval(&self.ghosts[index], self.elroy_stage());
+            if self.ghosts[index].move_timer >= interval {
+                self.ghosts[index].move_timer -= interval; // Keep the remainder for the next move
+                self.move_ghost(index);
+                // Check for collisions with ghosts
+                self.check_ghosts_collision();
+            }
         }
-        
+
         if let Some(interval) = self.switch_state_interval {
             if self.state_timer >= interval {
                 self.switch_state_interval = self.state_intervals.pop_front();
@@ -457,8 +736,383 @@ impl Game {
                     println!("Switching state for ghost: {} in {} s", ghost.name, self.state_timer);
                     ghost.state = self.ghosts_state;
                 }
+                self.reverse_ghosts();
                 self.state_timer = 0.0;
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{MAP_FILE, ROSTER_FILE};
+
+    fn new_game(seed: u64) -> Game {
+        Game::new(MAP_FILE, Roster::load(ROSTER_FILE).unwrap(), Ruleset::default(), seed).unwrap()
+    }
+
+    // Score, lives and where everybody stands
+    fn snapshot(game: &Game) -> String {
+        let pacman = game.pacman();
+        let ghosts: Vec<(i32, i32)> = game.ghosts().iter().map(|ghost| ghost.pos).collect();
+        format!("{} {} {:?} {:?}", pacman.score, pacman.lives, pacman.pos, ghosts)
+    }
+
+    // Starts on a power pellet so the frightened ghosts draw from the rng, then plays the same inputs
+    fn play(seed: u64) -> String {
+        let mut game = new_game(seed);
+        let power_pellet = game.grid.get_tiles().iter().find(|tile| tile.type_ == TileType::PowerPellet).unwrap().pos;
+        game.pacman.pos = power_pellet;
+        game.check_collision();
+        assert_eq!(game.game_state, GameState::Frightened);
+
+        let script = [(0, Direction::Right), (300, Direction::Down), (700, Direction::Left), (1200, Direction::Up)];
+        for tick in 0..3000 {
+            if let Some(&(_, direction)) = script.iter().find(|(at, _)| *at == tick) {
+                game.handle_input(Input::Move(direction));
+            }
+            game.step();
+        }
+        snapshot(&game)
+    }
+
+    // Puts Pacman on the next uneaten pellets one after the other
+    fn eat_pellets(game: &mut Game, count: usize) {
+        let pellets: Vec<(i32, i32)> = game
+            .grid
+            .get_tiles()
+            .iter()
+            .filter(|tile| tile.type_ == TileType::Pellet && game.grid.has_pellet(tile.pos))
+            .map(|tile| tile.pos)
+            .take(count)
+            .collect();
+        for pos in pellets {
+            game.pacman.pos = pos;
+            game.check_collision();
+        }
+    }
+
+    fn house(game: &Game, name: &str) -> HouseState {
+        game.ghosts.iter().find(|ghost| ghost.name == name).unwrap().house
+    }
+
+    // Steps until Blinky moves and tells which way he went
+    fn next_blinky_move(game: &mut Game) -> Direction {
+        let index = game.ghosts.iter().position(|ghost| ghost.name == "Blinky").unwrap();
+        let pos = game.ghosts[index].pos;
+        while game.ghosts[index].pos == pos {
+            game.step();
+        }
+        game.ghosts[index].direction
+    }
+
+    #[test]
+    fn runs_without_a_window() {
+        let mut game = new_game(1);
+        let start = game.pacman().pos;
+        for _ in 0..60 {
+            game.update(1. / 60.);
+        }
+        assert_ne!(game.pacman().pos, start);
+        assert!(game.pacman().score > PACMAN_INITIAL_SCORE);
+        assert_eq!(*game.game_state(), GameState::Playing);
+    }
+
+    #[test]
+    fn the_same_seed_and_inputs_give_the_same_game() {
+        assert_eq!(play(7), play(7));
+    }
+
+    #[test]
+    fn a_replay_reproduces_the_game() {
+        let mut game = new_game(7);
+        let script = [(0, Direction::Left), (300, Direction::Up), (700, Direction::Right), (1200, Direction::Down)];
+        for tick in 0..2000 {
+            if let Some(&(_, direction)) = script.iter().find(|(at, _)| *at == tick) {
+                game.handle_input(Input::Move(direction));
+            }
+            game.step();
+        }
+
+        let mut replayed = Game::from_replay(&game.to_replay(), Roster::load(ROSTER_FILE).unwrap(), Ruleset::default()).unwrap();
+        for _ in 0..2000 {
+            replayed.step();
+        }
+
+        assert_eq!(snapshot(&replayed), snapshot(&game));
+        assert!(game.pacman().score > 0);
+    }
+
+    #[test]
+    fn a_fruit_appears_after_70_and_170_pellets() {
+        let mut game = new_game(1);
+        eat_pellets(&mut game, 69);
+        assert!(game.fruit().is_none());
+        eat_pellets(&mut game, 1);
+        let (fruit, spot) = game.fruit().expect("the first fruit should be out");
+        assert_eq!((fruit, Some(spot)), (Fruit::for_level(1), game.fruit_spot()));
+
+        let score = game.pacman().score;
+        game.pacman.pos = spot;
+        game.check_fruit_collision();
+        assert!(game.fruit().is_none());
+        assert!(game.pacman().score > score);
+        assert_eq!(game.collected_fruits(), [fruit]);
+
+        eat_pellets(&mut game, 99);
+        assert!(game.fruit().is_none());
+        eat_pellets(&mut game, 1);
+        assert!(game.fruit().is_some());
+    }
+
+    #[test]
+    fn an_uneaten_fruit_goes_away() {
+        let mut game = new_game(1);
+        game.ghosts.clear(); // Only the fruit timer matters here
+        eat_pellets(&mut game, 70);
+        let ticks = (FRUIT_DURATION / SIMULATION_TICK) as u64;
+        for _ in 0..ticks - 1 {
+            game.step();
+        }
+        assert!(game.fruit().is_some());
+        game.step();
+        game.step();
+        assert!(game.fruit().is_none());
+        assert_eq!(*game.game_state(), GameState::Playing);
+    }
+
+    #[test]
+    fn ghosts_leave_the_house_on_their_dot_counters() {
+        let mut game = new_game(1);
+        assert_eq!(house(&game, "Blinky"), HouseState::Out);
+        assert_eq!(house(&game, "Pinky"), HouseState::Waiting);
+
+        game.release_ghosts(SIMULATION_TICK); // Pinky waits for no pellet
+        assert_eq!(house(&game, "Pinky"), HouseState::Leaving);
+
+        for (name, limit) in [("Inky", 30), ("Clyde", 60)] {
+            for _ in 0..limit - 1 {
+                game.count_dot();
+            }
+            game.release_ghosts(SIMULATION_TICK);
+            assert_eq!(house(&game, name), HouseState::Waiting);
+            game.count_dot();
+            game.release_ghosts(SIMULATION_TICK);
+            assert_eq!(house(&game, name), HouseState::Leaving);
+        }
+    }
+
+    #[test]
+    fn two_ghosts_on_pacman_only_cost_one_life() {
+        let mut game = new_game(1);
+        let lives = game.pacman().lives;
+        for ghost in game.ghosts.iter_mut().take(2) {
+            ghost.pos = game.pacman.pos;
+            ghost.state = GhostState::Chase;
+        }
+
+        game.check_ghosts_collision();
+        assert_eq!((game.pacman().lives, *game.game_state()), (lives - 1, GameState::Restarting));
+        game.step();
+        assert_eq!(game.pacman().lives, lives - 1);
+    }
+
+    #[test]
+    fn after_a_death_ghosts_leave_on_the_global_counter() {
+        let mut game = new_game(1);
+        game.release_ghosts(SIMULATION_TICK);
+        let blinky = game.ghosts.iter_mut().find(|ghost| ghost.name == "Blinky").unwrap();
+        blinky.pos = game.pacman.pos;
+        game.check_ghosts_collision();
+        assert_eq!(game.game_state, GameState::Restarting);
+        game.restart_game();
+
+        let mut eaten = 0;
+        for (name, limit) in [("Pinky", 7), ("Inky", 17), ("Clyde", 32)] {
+            while eaten < limit - 1 {
+                game.count_dot();
+                eaten += 1;
+            }
+            game.release_ghosts(SIMULATION_TICK);
+            assert_eq!(house(&game, name), HouseState::Waiting);
+            game.count_dot();
+            eaten += 1;
+            game.release_ghosts(SIMULATION_TICK);
+            assert_eq!(house(&game, name), HouseState::Leaving);
+        }
+        assert_eq!(game.global_dot_counter, None);
+    }
+
+    #[test]
+    fn not_eating_releases_the_next_ghost() {
+        let mut game = new_game(1);
+        game.release_ghosts(SIMULATION_TICK);
+        let ticks = (NO_PELLET_RELEASE_TIME / SIMULATION_TICK) as usize;
+        for _ in 0..ticks - 2 {
+            game.release_ghosts(SIMULATION_TICK);
+        }
+        game.count_dot(); // A pellet starts the wait again
+        for _ in 0..ticks - 2 {
+            game.release_ghosts(SIMULATION_TICK);
+        }
+        assert_eq!(house(&game, "Inky"), HouseState::Waiting);
+        for _ in 0..4 {
+            game.release_ghosts(SIMULATION_TICK);
+        }
+        assert_eq!(house(&game, "Inky"), HouseState::Leaving);
+        assert_eq!(house(&game, "Clyde"), HouseState::Waiting);
+    }
+
+    #[test]
+    fn blinky_turns_into_cruise_elroy_as_the_maze_empties() {
+        let mut game = new_game(1);
+        let total = game.remaining_pellets();
+        eat_pellets(&mut game, total - 21);
+        assert_eq!(game.elroy_stage(), 0);
+        eat_pellets(&mut game, 1);
+        assert_eq!(game.elroy_stage(), 1);
+        eat_pellets(&mut game, 9);
+        assert_eq!(game.elroy_stage(), 1);
+        eat_pellets(&mut game, 1);
+        assert_eq!((game.remaining_pellets(), game.elroy_stage()), (10, 2));
+
+        // Later levels start earlier
+        game.level = 3;
+        game.grid.reset_pellets();
+        eat_pellets(&mut game, total - 40);
+        assert_eq!(game.elroy_stage(), 1);
+    }
+
+    #[test]
+    fn cruise_elroy_waits_for_the_ghosts_after_a_death() {
+        let mut game = new_game(1);
+        let total = game.remaining_pellets();
+        eat_pellets(&mut game, total - 20);
+        assert_eq!(game.elroy_stage(), 1);
+
+        game.global_dot_counter = Some(0);
+        assert_eq!(game.elroy_stage(), 0);
+        for _ in 0..game.ghosts[3].global_dot_limit {
+            game.count_dot();
+            game.release_ghosts(SIMULATION_TICK);
+        }
+        assert_eq!(game.elroy_stage(), 1);
+    }
+
+    #[test]
+    fn ghosts_turn_back_when_scatter_ends() {
+        let mut game = new_game(1);
+        next_blinky_move(&mut game);
+        let direction = next_blinky_move(&mut game);
+
+        game.state_timer = game.switch_state_interval.unwrap();
+        assert_eq!(next_blinky_move(&mut game), direction.opposite());
+        assert!(game.ghosts_state == GhostState::Chase);
+    }
+
+    #[test]
+    fn ghosts_turn_back_when_frightened() {
+        let mut game = new_game(1);
+        next_blinky_move(&mut game);
+        let direction = next_blinky_move(&mut game);
+
+        let power_pellet = game.grid.get_tiles().iter().find(|tile| tile.type_ == TileType::PowerPellet).unwrap().pos;
+        game.pacman.pos = power_pellet;
+        game.check_collision();
+        assert_eq!(next_blinky_move(&mut game), direction.opposite());
+    }
+
+    #[test]
+    fn an_eaten_ghost_goes_home_and_leaves_again() {
+        let mut game = new_game(1);
+        let index = game.ghosts.iter().position(|ghost| ghost.name == "Blinky").unwrap();
+        game.ghosts[index].pos = game.pacman_spawn;
+        game.ghosts[index].state = GhostState::Eaten;
+
+        // Straight home along the shortest path
+        let distance = game.grid.maze_distance(game.pacman_spawn, game.ghost_house).unwrap();
+        for _ in 0..distance {
+            game.move_ghost(index);
+        }
+        assert_eq!(game.ghosts[index].pos, game.ghost_house);
+        // One more move to revive in the house
+        game.move_ghost(index);
+        assert!(game.ghosts[index].state == GhostState::Scatter);
+        assert_eq!(game.ghosts[index].house, HouseState::Leaving);
+
+        for _ in 0..20 {
+            game.move_ghost(index);
+        }
+        assert_eq!(game.ghosts[index].house, HouseState::Out);
+        assert!(!game.house_tiles.contains(&game.ghosts[index].pos));
+    }
+
+    #[test]
+    fn losing_the_focus_during_the_countdown_pauses_again() {
+        let mut game = new_game(1);
+        game.handle_input(Input::Pause);
+        game.handle_input(Input::Pause);
+        game.step();
+        assert!(game.resume_countdown.is_some());
+
+        game.pause();
+        assert_eq!(game.resume_countdown, None);
+        for _ in 0..(RESUME_COUNTDOWN / SIMULATION_TICK) as usize * 2 {
+            game.step();
+        }
+        assert_eq!((game.tick(), *game.game_state()), (0, GameState::Paused));
+    }
+
+    #[test]
+    fn extra_lives_stop_at_the_cap() {
+        let mut game = new_game(1);
+        game.rules.extra_life_repeat = true;
+        game.rules.max_lives = PACMAN_INITIAL_LIVES;
+        game.pacman.lose_life();
+
+        // Five thresholds crossed at once, but only one life fits under the cap
+        game.award_points(game.rules.extra_life_score * 5);
+        assert_eq!(game.pacman().lives, PACMAN_INITIAL_LIVES);
+        game.award_points(game.rules.extra_life_score);
+        assert_eq!(game.pacman().lives, PACMAN_INITIAL_LIVES);
+    }
+
+    #[test]
+    fn a_pause_stops_the_clock_until_the_countdown_ends() {
+        let mut game = new_game(1);
+        game.step();
+        game.handle_input(Input::Pause);
+        for _ in 0..100 {
+            game.step();
+        }
+        assert_eq!((game.tick(), *game.game_state()), (1, GameState::Paused));
+
+        game.handle_input(Input::Pause);
+        let mut countdown_ticks: u64 = 0;
+        while *game.game_state() == GameState::Paused {
+            game.step();
+            countdown_ticks += 1;
+        }
+        // Within a tick of the countdown, the float sum may fall either side
+        assert!(countdown_ticks.abs_diff((RESUME_COUNTDOWN / SIMULATION_TICK).round() as u64) <= 1);
+        assert_eq!((game.tick(), *game.game_state()), (1, GameState::Playing));
+    }
+
+    #[test]
+    fn a_slow_frame_only_runs_a_capped_number_of_ticks() {
+        let mut game = new_game(1);
+        game.update(5.0);
+        let ticks = game.tick();
+        assert!(ticks > 0 && ticks <= (MAX_FRAME_TIME / SIMULATION_TICK).round() as u64);
+        game.update(SIMULATION_TICK * 0.5);
+        assert_eq!(game.tick(), ticks);
+    }
+
+    #[test]
+    fn rejects_a_roster_that_does_not_fit_the_map() {
+        let mut roster = Roster::load(ROSTER_FILE).unwrap();
+        roster.ghosts[0].spawn = crate::roster::RosterPos::Tile((0, 0));
+        assert!(matches!(Game::new(MAP_FILE, roster, Ruleset::default(), 1), Err(GameError::Roster { .. })));
+    }
+}
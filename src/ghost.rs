@@ -1,85 +1,177 @@
-use crate::enums::{Direction, GhostState};
-use crate::grid::Grid;
-use crate::constants::{BOTTOM_LEFT_CORNER, CELL_SIZE, GRID_HEIGHT, GRID_WIDTH };
-use crate::utils::manhattan_distance;
-
-pub fn calculate_inky_vec(ahead: (i32, i32), blinky_pos: (i32, i32)) -> (i32, i32) {
-    // Inky's target is two tiles in front of Pacman plus the vector from Inky to Blinky
-    let dx = ahead.0 - blinky_pos.0;
-    let dy = ahead.1 - blinky_pos.1;
-    (ahead.0 + 2 * dx, ahead.1 + 2* dy)
-}
-
-pub fn get_target_inky(pacman_dir: Direction, pacman_pos: (i32, i32), blinky_pos: (i32, i32)) -> (i32, i32) {
-    let (pacman_x, pacman_y) = pacman_pos;
-    // Inky's target is two tiles in front of Pacman
-    let dx = match pacman_dir {
-        Direction::Left => -1,
-        Direction::Right => 1,
-        _ => 0,
-    };
-    let dy = match pacman_dir {
-        Direction::Up => -1,
-        Direction::Down => 1,
-        _ => 0,
-    };
-
-    let ahead = (pacman_x + 2 * dx, pacman_y + 2 * dy);
-    calculate_inky_vec(ahead, blinky_pos)
-}
+use rand::Rng;
 
-pub fn get_target_pinky(pacman_dir:Direction, pacman_pos: (i32, i32)) -> (i32, i32) {
-    let dx = match pacman_dir {
-        Direction::Left => -1,
-        Direction::Right => 1,
-        _ => 0,
-    };
-    let dy = match pacman_dir {
-        Direction::Up => -1,
-        Direction::Down => 1,
-        _ => 0,
-    };
-
-    (pacman_pos.0 + 4 * dx, pacman_pos.1 + 4 * dy)
-}
-
-pub fn get_target_clyde(pacman_pos: (i32, i32), clyde_pos: (i32, i32)) -> (i32, i32) {
-    // If Clyde is far from Pacman, chase Pacman
-    if manhattan_distance(pacman_pos, clyde_pos) > 8. {
-        pacman_pos
-    } else {
-        // Otherwise, scatter to the bottom left corner
-        BOTTOM_LEFT_CORNER
-    }
-}
+use crate::brain::GhostBrain;
+use crate::enums::{Direction, GhostState, HouseState, Targeting};
+use crate::grid::{Grid, Walkability};
+use crate::constants::CELL_SIZE;
+use crate::roster::GhostConfig;
+use crate::utils::manhattan_distance;
 
 pub struct Ghost {
     pub name: String,
     pub pos: (i32, i32),
+    pub spawn_pos: (i32, i32),
     pub direction: Direction,
     pub state: GhostState,
     pub scatter_pos: (i32, i32),
     pub color: [f32; 4], // RGBA color
+    pub house: HouseState,
+    pub dot_counter: usize, // Pellets eaten while this ghost was next to leave the house
+    pub dot_limits: Vec<usize>, // Pellets to wait for in the house, per level
+    pub global_dot_limit: usize, // Pellets to wait for after a death, on the global counter
+    pub elroy: bool, // Turns into Cruise Elroy when the maze empties
+    pub move_timer: f64, // Time accumulated towards the next move, each ghost has its own pace
+    pub targeting: Targeting,
+    brain: Box<dyn GhostBrain>, // Where the ghost goes in Chase mode
+    reverse_pending: bool,
 }
 
 impl Ghost {
     // Option 1: Accept a boxed state directly
-    pub fn new(name: String, pos: (i32, i32), scatter_pos: (i32, i32), color: [f32; 4]) -> Self {
+    pub fn new(name: String, pos: (i32, i32), scatter_pos: (i32, i32), color: [f32; 4], brain: Box<dyn GhostBrain>) -> Self {
         Ghost { 
             name, 
             pos, 
+            spawn_pos: pos,
             state: GhostState::Scatter,
             direction: Direction::Left, // Default direction
             scatter_pos,
-            color
+            color,
+            house: HouseState::Out,
+            dot_counter: 0,
+            dot_limits: vec![0],
+            global_dot_limit: 0,
+            elroy: false,
+            move_timer: 0.0,
+            targeting: Targeting::Manhattan,
+            brain,
+            reverse_pending: false,
         }
     }
     
+    // Ghost of the roster, at its spawn and scatter corner on the map
+    pub fn from_config(config: &GhostConfig, pos: (i32, i32), scatter_pos: (i32, i32)) -> Self {
+        let mut ghost = Ghost::new(config.name.clone(), pos, scatter_pos, config.color, config.personality.brain());
+        ghost.dot_limits = config.dot_limits.clone();
+        ghost.global_dot_limit = config.global_dot_limit;
+        ghost.targeting = config.targeting;
+        ghost.elroy = config.elroy;
+        ghost
+    }
+
+    // Pellets the ghost waits for in the house before leaving, the last limit holds for the later levels
+    pub fn dot_limit(&self, level: usize) -> usize {
+        let index = level.saturating_sub(1).min(self.dot_limits.len().saturating_sub(1));
+        self.dot_limits.get(index).copied().unwrap_or(0)
+    }
+
+    pub fn brain(&self) -> &dyn GhostBrain {
+        self.brain.as_ref()
+    }
+
+    // Swap the personality, to try a new behaviour
+    pub fn set_brain(&mut self, brain: Box<dyn GhostBrain>) {
+        self.brain = brain;
+    }
+
+    // Only ghosts leaving the house or going back to it may cross the gate
+    pub fn can_cross_gate(&self) -> bool {
+        self.house == HouseState::Leaving || self.state == GhostState::Eaten
+    }
+
+    // Back to the spawn point, as at the start of a life
+    pub fn respawn(&mut self) {
+        self.pos = self.spawn_pos;
+        self.state = GhostState::Scatter;
+        self.direction = Direction::Left;
+        self.move_timer = 0.0;
+        self.reverse_pending = false;
+    }
+
+    // Forced to turn back on its next move, when the ghosts change mode
+    pub fn reverse(&mut self) {
+        self.reverse_pending = true;
+    }
+
     pub fn move_around(&mut self, target: (i32, i32),grid: &Grid) {
+        if self.take_reversal(grid) {
+            return;
+        }
+
         if let Some(new_pos) = self.move_to_target(target, grid) {
             self.pos = new_pos; // Move to the next node in the path
         }
     }
+
+    // Go in a direction chosen by a brain, straight on when it is not possible, and the first
+    // legal turn when going straight is blocked too
+    pub fn move_in_direction(&mut self, direction: Direction, grid: &Grid) {
+        if self.take_reversal(grid) {
+            return;
+        }
+
+        let moves = self.possible_moves(grid);
+        let chosen = moves
+            .iter()
+            .find(|(d, _)| *d == direction)
+            .or_else(|| moves.iter().find(|(d, _)| *d == self.direction))
+            .or(moves.first());
+        if let Some(&(direction, pos)) = chosen {
+            self.direction = direction;
+            self.pos = pos;
+        }
+    }
+
+    // Frightened ghosts wander: a pseudo-random legal turn at intersections, never back
+    pub fn move_randomly(&mut self, grid: &Grid, rng: &mut impl Rng) {
+        if self.take_reversal(grid) {
+            return;
+        }
+
+        let moves = self.possible_moves(grid);
+        if moves.is_empty() {
+            return;
+        }
+
+        // In a corridor there is only one way to go, the RNG is only drawn at intersections
+        let (direction, pos) = if moves.len() == 1 { moves[0] } else { moves[rng.random_range(0..moves.len())] };
+        self.direction = direction;
+        self.pos = pos;
+    }
+
+    // Step onto a neighbouring tile, false if it is not one step away
+    pub fn step_to(&mut self, next: (i32, i32), grid: &Grid) -> bool {
+        let direction = [Direction::Up, Direction::Left, Direction::Down, Direction::Right]
+            .into_iter()
+            .find(|&direction| grid.next_pos(self.pos, direction) == next);
+
+        match direction {
+            Some(direction) => {
+                self.direction = direction;
+                self.pos = next;
+                self.reverse_pending = false; // The path already chose the way to go
+                true
+            },
+            None => false,
+        }
+    }
+
+    // Forced reversal after a mode change, true if the ghost turned back
+    fn take_reversal(&mut self, grid: &Grid) -> bool {
+        if !self.reverse_pending {
+            return false;
+        }
+        self.reverse_pending = false;
+
+        let reversed = self.direction.opposite();
+        let back = grid.next_pos(self.pos, reversed);
+        if grid.get_tile(back).is_some_and(|tile| self.walkability().allows(tile)) {
+            self.direction = reversed;
+            self.pos = back;
+            return true;
+        }
+        false
+    }
     
     pub fn get_pixels_x(&self) -> i32 {
         self.pos.0 * CELL_SIZE
@@ -89,80 +181,109 @@ impl Ghost {
         self.pos.1 * CELL_SIZE
     }
 
-    fn determine_direction(&mut self, new_pos: (i32, i32)) -> Direction {
-        match self.pos.0 - new_pos.0 {
-            0 => match self.pos.1 - new_pos.1 {
-                0 => Direction::Right, // Moving right
-                1 => Direction::Down, // Moving down
-                -1 => Direction::Up, // Moving up
-                _ => Direction::Up, // Default to left if no movement
-            }, // Moving down
-            1 => Direction::Right, // Moving right
-            -1 => Direction::Left, // Moving left
-            _ => Direction::Right, 
+    // Tiles the ghost may step on, the gate only opens to ghosts allowed through it
+    pub fn walkability(&self) -> Walkability {
+        if self.can_cross_gate() { Walkability::EatenGhost } else { Walkability::Ghost }
+    }
+
+    // Moves the ghost may take from its tile, in the arcade tie-break order: up, left, down, right
+    fn possible_moves(&self, game_grid: &Grid) -> Vec<(Direction, (i32, i32))> {
+        // A ghost never turns back on its own
+        let reverse = self.direction.opposite();
+        // Nor turns upward in a red zone while chasing or scattering
+        let no_upward = game_grid.is_red_zone(self.pos)
+            && self.house == HouseState::Out
+            && matches!(self.state, GhostState::Chase | GhostState::Scatter);
+
+        [Direction::Up, Direction::Left, Direction::Down, Direction::Right]
+            .into_iter()
+            .filter(|&direction| direction != reverse)
+            .filter(|&direction| direction != Direction::Up || !no_upward)
+            .map(|direction| (direction, game_grid.next_pos(self.pos, direction)))
+            .filter(|(_, pos)| {
+                // Check if the position is within bounds and walkable
+                game_grid.get_tile(*pos).is_some_and(|tile| self.walkability().allows(tile))
+            })
+            .collect()
+    }
+
+    // Distance from a tile to the target the way this ghost measures it. Targets the maze
+    // does not reach (inside walls, off the grid) fall back to the Manhattan distance
+    fn distance_to_target(&self, pos: (i32, i32), target: (i32, i32), game_grid: &Grid) -> f32 {
+        match self.targeting {
+            Targeting::MazeDistance => game_grid
+                .maze_distance(pos, target)
+                .map(|distance| distance as f32)
+                .unwrap_or_else(|| manhattan_distance(pos, target)),
+            Targeting::Manhattan => manhattan_distance(pos, target),
         }
     }
 
     pub fn move_to_target(&mut self, target:(i32, i32), game_grid: &Grid) -> Option<(i32, i32)> {
-        let (ghost_x, ghost_y) = self.pos;
-        let up = (ghost_x, if ghost_y - 1 < 0 { GRID_HEIGHT - 1 } else { ghost_y - 1 });
-        let down = (ghost_x, if ghost_y + 1 >= GRID_HEIGHT { 0 } else { ghost_y + 1 });
-        let left = (if ghost_x - 1 < 0 { GRID_WIDTH - 1 } else { ghost_x - 1}, ghost_y);
-        let right = (if ghost_x + 1 >= GRID_WIDTH { 0 } else { ghost_x + 1 }, ghost_y);
-
-        let mut possible_moves = vec![
-            up, // Up
-            down, // Down
-            left, // Left
-            right, // Right
-        ];
-
-        let oposite_dir = self.direction.opposite();
-        match oposite_dir {
-            Direction::Up => {
-                // If the ghost is moving up, it cannot move down
-                possible_moves.retain(|&pos| pos != down);
-            },
-            Direction::Down => {
-                // If the ghost is moving down, it cannot move up
-                possible_moves.retain(|&pos| pos != up);
-            },
-            Direction::Left => {
-                // If the ghost is moving left, it cannot move right
-                possible_moves.retain(|&pos| pos != right);
-            },
-            Direction::Right => {
-                // If the ghost is moving right, it cannot move left
-                possible_moves.retain(|&pos| pos != left);
-            },
-        }
-        
-        possible_moves.iter().filter(|position| {
-            // Check if the position is within bounds and walkable
-            if let Some(tile) = game_grid.get_tile(**position) {
-                tile.is_walkable_for_ghost()
-            } else {
-                false
-            }
-        }).map(|&pos| {
-            // Calculate the distance to the player
-            let distance = manhattan_distance(pos, target);
-            
-            (pos, distance, self.determine_direction(target) as i32)
-        }).min_by(|a, b| {
-            // First compare by distance
-            match a.1.partial_cmp(&b.1) {
-                Some(std::cmp::Ordering::Equal) => {
-                    // If distances are equal, compare by direction priority
-                    a.2.cmp(&b.2)
-                },
-                Some(order) => order,
-                None => std::cmp::Ordering::Equal,
-            }
-        }).map(|(best_move, _, _)| {
-            self.direction = self.determine_direction(best_move);
-            best_move
+        let best_move = self.possible_moves(game_grid)
+            .into_iter()
+            .min_by(|a, b| {
+                // Closest to the target wins, the first one in priority order on ties
+                self.distance_to_target(a.1, target, game_grid)
+                    .partial_cmp(&self.distance_to_target(b.1, target, game_grid))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+
+        best_move.map(|(direction, pos)| {
+            self.direction = direction;
+            pos
         })
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brain::Shadow;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::constants::MAP_FILE;
+    use crate::utils::test_file;
+
+    #[test]
+    fn frightened_ghosts_only_turn_at_intersections_and_never_back() {
+        let grid = Grid::new(MAP_FILE, CELL_SIZE).unwrap();
+        let start = grid.markers().pacman_spawn.unwrap();
+        let mut ghost = Ghost::new("Test".to_string(), start, (0, 0), [1.0; 4], Box::new(Shadow));
+        ghost.state = GhostState::Frightened;
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut turns = 0;
+
+        for _ in 0..1000 {
+            let (pos, direction) = (ghost.pos, ghost.direction);
+            let moves = ghost.possible_moves(&grid);
+            ghost.move_randomly(&grid, &mut rng);
+
+            assert_ne!(ghost.direction, direction.opposite(), "turned back at {:?}", pos);
+            if moves.len() == 1 {
+                // Corridors and corners leave a single way to go
+                assert_eq!((ghost.direction, ghost.pos), moves[0]);
+            } else if ghost.direction != direction {
+                turns += 1;
+            }
+        }
+        assert!(turns > 0);
+    }
+
+    #[test]
+    fn an_illegal_brain_direction_keeps_the_ghost_going_straight() {
+        // A corridor with a side opening above the ghost
+        let grid = Grid::new(&test_file("ghost-straight.map", "###.###\n#P....#\n#######\n"), CELL_SIZE).unwrap();
+        let mut ghost = Ghost::new("Test".to_string(), (2, 1), (0, 0), [1.0; 4], Box::new(Shadow));
+        ghost.house = HouseState::Out;
+        ghost.state = GhostState::Chase;
+        ghost.direction = Direction::Right;
+
+        ghost.move_in_direction(Direction::Down, &grid); // Wall below
+        assert_eq!((ghost.pos, ghost.direction), ((3, 1), Direction::Right));
+
+        ghost.move_in_direction(Direction::Up, &grid); // Open above
+        assert_eq!((ghost.pos, ghost.direction), ((3, 0), Direction::Up));
+    }
+}
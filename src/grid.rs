@@ -1,5 +1,7 @@
-use std::{fs::File, io::{BufRead, BufReader}};
+use std::{cell::RefCell, collections::{HashMap, HashSet, VecDeque}, error::Error, fmt, fs::File, io::{self, BufRead, BufReader}};
 use ndarray::Array2;
+use crate::constants::DISTANCE_CACHE_SIZE;
+use crate::enums::Direction;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum TileType {
@@ -10,16 +12,27 @@ pub enum TileType {
     GhostGate,
 }
 
+// Special areas of the maze, from the zone layer of the map file
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Zone {
+    None,
+    // Ghosts slow down in the side tunnels
+    Tunnel,
+    // Ghosts in Chase or Scatter may not turn upward here
+    RedZone,
+}
+
 #[derive(Clone)]
 pub struct Tile {
     pub pos: (i32, i32),
     size: (i32, i32),
-    pub type_: TileType
+    pub type_: TileType,
+    pub zone: Zone,
 }
 
 impl Tile {
     pub fn new(pos: (i32, i32), size: (i32, i32), type_: TileType) -> Self {
-        Tile { pos, size, type_ }
+        Tile { pos, size, type_, zone: Zone::None }
     }
 
     pub fn get_pos(&self) -> (i32, i32) {
@@ -42,30 +55,146 @@ impl Tile {
         &self.type_
     }
 
+    pub fn is_pellet(&self) -> bool {
+        matches!(self.type_, TileType::Pellet | TileType::PowerPellet)
+    }
+
     pub fn is_walkable_for_pacman(&self) -> bool {
-        match self.type_ {
-            TileType::Wall => false,
-            TileType::GhostGate => false,
-            _ => true,
-        }
+        !matches!(self.type_, TileType::Wall | TileType::GhostGate)
     }
 
     pub fn is_walkable_for_ghost(&self) -> bool {
-        match self.type_ {
-            TileType::Wall => false,
-            _ => true,
+        !matches!(self.type_, TileType::Wall)
+    }
+}
+
+// Which tiles an agent may walk on, so the same pathfinding serves every agent
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Walkability {
+    Pacman,
+    // Ghosts in the maze, the gate is closed to them
+    Ghost,
+    // Eaten ghosts (and ghosts leaving the house) go through the gate
+    EatenGhost,
+}
+
+impl Walkability {
+    pub fn allows(self, tile: &Tile) -> bool {
+        match self {
+            Walkability::Pacman => tile.is_walkable_for_pacman(),
+            Walkability::Ghost => tile.is_walkable_for_ghost() && tile.type_ != TileType::GhostGate,
+            Walkability::EatenGhost => tile.is_walkable_for_ghost(),
         }
     }
 }
 
- fn char_to_tile_type(c: char) -> TileType {
+fn char_to_tile_type(c: char) -> Option<TileType> {
+    match c {
+        '#' => Some(TileType::Wall),
+        '.' => Some(TileType::Pellet),
+        ' ' => Some(TileType::Floor),
+        'o' => Some(TileType::PowerPellet),
+        '=' => Some(TileType::GhostGate), // 👈 Gate from the map
+        // Scatter corners are targets, they usually sit in the outer wall
+        'b' | 'k' | 'i' | 'c' => Some(TileType::Wall),
+        // Spawn points, ghost house and fruit spot are empty floor
+        'P' | 'B' | 'K' | 'I' | 'C' | 'H' | 'F' => Some(TileType::Floor),
+        _   => None,
+    }
+}
+
+fn char_to_zone(c: char) -> Option<Zone> {
     match c {
-        '#' => TileType::Wall,
-        '.' => TileType::Pellet,
-        ' ' => TileType::Floor,
-        'o' => TileType::PowerPellet,
-        '=' => TileType::GhostGate, // 👈 Gate from the map
-        _   => TileType::Floor,
+        ' ' | '.' => Some(Zone::None),
+        'T' => Some(Zone::Tunnel),
+        'R' => Some(Zone::RedZone),
+        _ => None,
+    }
+}
+
+// Everything that can go wrong while loading a map file.
+// Lines and columns are 1-based, as shown by text editors.
+#[derive(Debug)]
+pub enum MapError {
+    MissingFile { path: String, source: io::Error },
+    Io { line: usize, source: io::Error },
+    Empty,
+    RaggedRow { line: usize, expected: usize, found: usize },
+    UnknownGlyph { line: usize, column: usize, glyph: char },
+    ZoneOutOfBounds { line: usize },
+    MissingPacmanSpawn,
+    UnreachablePellets(Vec<(i32, i32)>),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MapError::MissingFile { source, .. } => write!(f, "could not open the file ({})", source),
+            MapError::Io { line, source } => write!(f, "line {}: could not read the file ({})", line, source),
+            MapError::Empty => write!(f, "the map file is empty"),
+            MapError::RaggedRow { line, expected, found } => {
+                write!(f, "line {}: row is {} tiles wide, expected {} like the first row", line, found, expected)
+            },
+            MapError::UnknownGlyph { line, column, glyph } => {
+                write!(f, "line {}, column {}: unknown glyph {:?}", line, column, glyph)
+            },
+            MapError::ZoneOutOfBounds { line } => write!(f, "line {}: the zone layer is larger than the map", line),
+            MapError::MissingPacmanSpawn => write!(f, "the map has no Pacman spawn (P)"),
+            MapError::UnreachablePellets(positions) => {
+                write!(f, "{} pellet(s) cannot be reached from the Pacman spawn:", positions.len())?;
+                for (col, row) in positions {
+                    write!(f, " (line {}, column {})", row + 1, col + 1)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl Error for MapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MapError::MissingFile { source, .. } | MapError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// Positions read from the marker glyphs of the map file:
+//   P: Pacman spawn
+//   B, K, I, C: Blinky, Pinky, Inky and Clyde spawns
+//   b, k, i, c: Blinky, Pinky, Inky and Clyde scatter corners
+//   H: ghost house, where eaten ghosts go back to
+//   F: fruit spot
+#[derive(Clone, Debug, Default)]
+pub struct MapMarkers {
+    pub pacman_spawn: Option<(i32, i32)>,
+    pub ghost_spawns: HashMap<char, (i32, i32)>,
+    pub scatter_corners: HashMap<char, (i32, i32)>,
+    pub ghost_house: Option<(i32, i32)>,
+    pub fruit_spot: Option<(i32, i32)>,
+}
+
+impl MapMarkers {
+    fn record(&mut self, c: char, pos: (i32, i32)) {
+        match c {
+            'P' => self.pacman_spawn = Some(pos),
+            'B' | 'K' | 'I' | 'C' => { self.ghost_spawns.insert(c, pos); },
+            'b' | 'k' | 'i' | 'c' => { self.scatter_corners.insert(c.to_ascii_uppercase(), pos); },
+            'H' => self.ghost_house = Some(pos),
+            'F' => self.fruit_spot = Some(pos),
+            _ => {}
+        }
+    }
+
+    // Spawn of the ghost identified by its marker (B, K, I or C)
+    pub fn ghost_spawn(&self, marker: char) -> Option<(i32, i32)> {
+        self.ghost_spawns.get(&marker).copied()
+    }
+
+    // Scatter corner of the ghost identified by its spawn marker (B, K, I or C)
+    pub fn scatter_corner(&self, marker: char) -> Option<(i32, i32)> {
+        self.scatter_corners.get(&marker).copied()
     }
 }
 
@@ -73,28 +202,318 @@ pub struct Grid {
     tiles: Array2<Tile>,
     num_cols: i32,
     num_rows: i32,
+    markers: MapMarkers,
+    distance_cache: RefCell<HashMap<usize, Vec<u16>>>, // Maze distances to the recent targets, by target cell, see maze_distance
+    eaten: Vec<bool>, // Pellets eaten on this level, by cell
+    total_pellets: usize,
+    pellets_left: usize,
 }
 
-fn load_grid_from_file(path: &str, tile_size: i32, num_cols: i32, num_rows: i32) -> Array2<Tile> {
-    let file = File::open(path).expect("Could not open map file");
+// A map file is the tile rows, optionally followed by an empty line and a zone layer
+// laid over the tiles (shorter rows are padded with no zone):
+//   T: tunnel, ghosts slow down
+//   R: red zone, ghosts in Chase or Scatter cannot turn upward
+//   ' ' or '.': no zone
+
+fn load_grid_from_file(path: &str, tile_size: i32) -> Result<(Array2<Tile>, MapMarkers), MapError> {
+    let file = File::open(path).map_err(|source| MapError::MissingFile { path: path.to_string(), source })?;
     let reader = BufReader::new(file);
 
-    let grid: Vec<Vec<char>> = reader
+    // A read error must not pass for the end of the map, maps saved on Windows end their lines with \r\n
+    let lines = reader
         .lines()
-        .filter_map(Result::ok)
-        .map(|line| line.chars().collect())
-        .collect();
-        
-    Array2::from_shape_fn(
-        (num_cols as usize, num_rows as usize),
-        |(col, row)| Tile::new((col as i32, row as i32), (tile_size, tile_size), char_to_tile_type(grid[row][col])),
-    )  
+        .enumerate()
+        .map(|(index, line)| {
+            line.map(|line| line.strip_suffix('\r').unwrap_or(&line).chars().collect())
+                .map_err(|source| MapError::Io { line: index + 1, source })
+        })
+        .collect::<Result<Vec<Vec<char>>, MapError>>()?;
+
+    // The first empty line separates the tiles from the zone layer
+    let (grid, zones) = match lines.iter().position(|line| line.is_empty()) {
+        Some(separator) => (&lines[..separator], &lines[separator + 1..]),
+        None => (&lines[..], &lines[lines.len()..]),
+    };
+
+    // The map file defines the grid size: as many rows as lines, as many columns as the first line
+    let num_rows = grid.len();
+    let num_cols = grid.first().map(|line| line.len()).unwrap_or(0);
+    if num_rows == 0 || num_cols == 0 {
+        return Err(MapError::Empty);
+    }
+
+    let mut markers = MapMarkers::default();
+    let mut types = Vec::with_capacity(num_rows * num_cols);
+
+    for (row, line) in grid.iter().enumerate() {
+        if line.len() != num_cols {
+            return Err(MapError::RaggedRow { line: row + 1, expected: num_cols, found: line.len() });
+        }
+        for (col, &c) in line.iter().enumerate() {
+            let type_ = char_to_tile_type(c)
+                .ok_or(MapError::UnknownGlyph { line: row + 1, column: col + 1, glyph: c })?;
+            markers.record(c, (col as i32, row as i32));
+            types.push(type_);
+        }
+    }
+
+    let mut tiles = Array2::from_shape_fn(
+        (num_cols, num_rows),
+        |(col, row)| Tile::new((col as i32, row as i32), (tile_size, tile_size), types[row * num_cols + col].clone()),
+    );
+
+    for (row, line) in zones.iter().enumerate() {
+        let line_number = num_rows + 2 + row;
+        if (row >= num_rows && !line.is_empty()) || line.len() > num_cols {
+            return Err(MapError::ZoneOutOfBounds { line: line_number });
+        }
+        for (col, &c) in line.iter().enumerate() {
+            tiles[(col, row)].zone = char_to_zone(c)
+                .ok_or(MapError::UnknownGlyph { line: line_number, column: col + 1, glyph: c })?;
+        }
+    }
+
+    Ok((tiles, markers))
 }
 
 impl Grid {
-    pub fn new(path: &str, tile_size: i32, num_cols: i32, num_rows: i32) -> Self {
-        let tiles = load_grid_from_file(path, tile_size, num_cols, num_rows);
-        Grid { tiles, num_cols, num_rows }
+    // Load a map that is ready to be played
+    pub fn new(path: &str, tile_size: i32) -> Result<Self, MapError> {
+        let grid = Grid::load(path, tile_size)?;
+        grid.validate()?;
+        Ok(grid)
+    }
+
+    // Parse a map file without checking it can be played, used by the map analysis
+    pub fn load(path: &str, tile_size: i32) -> Result<Self, MapError> {
+        let (tiles, markers) = load_grid_from_file(path, tile_size)?;
+        let (num_cols, num_rows) = tiles.dim();
+        let total_pellets = tiles.iter().filter(|tile| tile.is_pellet()).count();
+        let grid = Grid {
+            eaten: vec![false; num_cols * num_rows],
+            tiles,
+            num_cols: num_cols as i32,
+            num_rows: num_rows as i32,
+            markers,
+            distance_cache: RefCell::new(HashMap::new()),
+            total_pellets,
+            pellets_left: total_pellets,
+        };
+        Ok(grid)
+    }
+
+    // Whether an uneaten pellet or power pellet lies on the tile
+    pub fn has_pellet(&self, pos: (i32, i32)) -> bool {
+        self.get_tile(pos).is_some_and(|tile| tile.is_pellet() && !self.eaten[self.cell_index(pos)])
+    }
+
+    // Eat the pellet on the tile, returns its type if there was one left
+    pub fn eat_pellet(&mut self, pos: (i32, i32)) -> Option<TileType> {
+        if !self.has_pellet(pos) {
+            return None;
+        }
+        let index = self.cell_index(pos);
+        self.eaten[index] = true;
+        self.pellets_left -= 1;
+        self.get_tile(pos).map(|tile| tile.type_.clone())
+    }
+
+    pub fn pellets_left(&self) -> usize {
+        self.pellets_left
+    }
+
+    pub fn pellets_eaten(&self) -> usize {
+        self.total_pellets - self.pellets_left
+    }
+
+    pub fn total_pellets(&self) -> usize {
+        self.total_pellets
+    }
+
+    // Put every pellet back, for a new level
+    pub fn reset_pellets(&mut self) {
+        self.eaten.fill(false);
+        self.pellets_left = self.total_pellets;
+    }
+
+    // Breadth-first search from one tile to every other, through the gate so eaten ghosts can use it too.
+    // The house is a dead end, so the gate never makes a shorter way for the ghosts in the maze
+    fn distances_from(&self, source: (i32, i32)) -> Vec<u16> {
+        let mut distances = vec![u16::MAX; (self.num_cols * self.num_rows) as usize];
+        let mut queue = VecDeque::from([source]);
+        distances[self.cell_index(source)] = 0;
+
+        while let Some(pos) = queue.pop_front() {
+            let distance = distances[self.cell_index(pos)] + 1;
+            for next in self.walkable_neighbors(pos, Walkability::EatenGhost) {
+                if distances[self.cell_index(next)] == u16::MAX {
+                    distances[self.cell_index(next)] = distance;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        distances
+    }
+
+    fn cell_index(&self, pos: (i32, i32)) -> usize {
+        (pos.1 * self.num_cols + pos.0) as usize
+    }
+
+    // Number of steps between two tiles through the maze and the tunnels, None if one of them
+    // is not walkable or they are not connected
+    pub fn maze_distance(&self, from: (i32, i32), to: (i32, i32)) -> Option<u32> {
+        let walkable = |pos: (i32, i32)| self.get_tile(pos).is_some_and(|tile| Walkability::EatenGhost.allows(tile));
+        if !walkable(from) || !walkable(to) {
+            return None;
+        }
+
+        // The maze is the same both ways, one search from the target answers every tile around it.
+        // Searches are kept for the targets asked recently, the cache starts over when full
+        let mut cache = self.distance_cache.borrow_mut();
+        let target = self.cell_index(to);
+        if cache.len() >= DISTANCE_CACHE_SIZE && !cache.contains_key(&target) {
+            cache.clear();
+        }
+        let distances = cache.entry(target).or_insert_with(|| self.distances_from(to));
+
+        match distances[self.cell_index(from)] {
+            u16::MAX => None,
+            distance => Some(distance as u32),
+        }
+    }
+    // Zone of a tile, no zone outside the grid
+    pub fn zone(&self, pos: (i32, i32)) -> Zone {
+        self.get_tile(pos).map(|tile| tile.zone).unwrap_or(Zone::None)
+    }
+
+    // Ghosts slow down in the tunnels
+    pub fn is_tunnel(&self, pos: (i32, i32)) -> bool {
+        self.zone(pos) == Zone::Tunnel
+    }
+
+    // Ghosts cannot turn upward in the red zones
+    pub fn is_red_zone(&self, pos: (i32, i32)) -> bool {
+        self.zone(pos) == Zone::RedZone
+    }
+
+    pub fn validate(&self) -> Result<(), MapError> {
+        // Every pellet must be reachable by Pacman, otherwise the level cannot be won
+        let pacman_spawn = self.markers.pacman_spawn.ok_or(MapError::MissingPacmanSpawn)?;
+        let unreachable_pellets = self.unreachable_pellets(pacman_spawn);
+        if !unreachable_pellets.is_empty() {
+            return Err(MapError::UnreachablePellets(unreachable_pellets));
+        }
+
+        Ok(())
+    }
+
+    // Pellets and power pellets Pacman cannot reach from start, in reading order
+    pub fn unreachable_pellets(&self, start: (i32, i32)) -> Vec<(i32, i32)> {
+        let reachable = self.reachable_from(start, Tile::is_walkable_for_pacman);
+        self.tiles
+            .t()
+            .iter()
+            .filter(|tile| tile.type_ == TileType::Pellet || tile.type_ == TileType::PowerPellet)
+            .filter(|tile| !reachable.contains(&tile.pos))
+            .map(|tile| tile.pos)
+            .collect()
+    }
+
+    // Tiles inside the ghost house, closed by the gate. Empty when Pacman can walk in,
+    // the house is then just part of the maze
+    pub fn ghost_house_tiles(&self, house: (i32, i32)) -> HashSet<(i32, i32)> {
+        let inside = self.reachable_from(house, Tile::is_walkable_for_pacman);
+        match self.markers.pacman_spawn {
+            Some(spawn) if inside.contains(&spawn) => HashSet::new(),
+            _ => inside,
+        }
+    }
+
+    // Walkable tile right outside the ghost gate, the closest one to the house
+    pub fn ghost_house_exit(&self, house: (i32, i32)) -> Option<(i32, i32)> {
+        let inside = self.ghost_house_tiles(house);
+        self.tiles
+            .t()
+            .iter()
+            .filter(|tile| tile.type_ == TileType::GhostGate)
+            .flat_map(|gate| {
+                [Direction::Up, Direction::Left, Direction::Down, Direction::Right]
+                    .map(|direction| self.next_pos(gate.pos, direction))
+            })
+            .filter(|pos| !inside.contains(pos) && self.get_tile(*pos).is_some_and(Tile::is_walkable_for_pacman))
+            .min_by_key(|pos| (pos.0 - house.0).abs() + (pos.1 - house.1).abs())
+    }
+
+    // Positions reachable from start (breadth-first, through the tunnels) moving only on walkable tiles
+    pub fn reachable_from(&self, start: (i32, i32), walkable: fn(&Tile) -> bool) -> HashSet<(i32, i32)> {
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+
+        visited.insert(start);
+        queue.push_back(start);
+
+        while let Some(pos) = queue.pop_front() {
+            for direction in [Direction::Up, Direction::Left, Direction::Down, Direction::Right] {
+                let next = self.next_pos(pos, direction);
+                if visited.contains(&next) {
+                    continue;
+                }
+                if self.get_tile(next).is_some_and(walkable) {
+                    visited.insert(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        visited
+    }
+
+    pub fn markers(&self) -> &MapMarkers {
+        &self.markers
+    }
+
+    // Number of columns
+    pub fn width(&self) -> i32 {
+        self.num_cols
+    }
+
+    // Number of rows
+    pub fn height(&self) -> i32 {
+        self.num_rows
+    }
+
+    pub fn in_bounds(&self, pos: (i32, i32)) -> bool {
+        pos.0 >= 0 && pos.0 < self.num_cols && pos.1 >= 0 && pos.1 < self.num_rows
+    }
+
+    // Wrap a position around the grid edges (tunnels)
+    pub fn wrap(&self, pos: (i32, i32)) -> (i32, i32) {
+        (pos.0.rem_euclid(self.num_cols), pos.1.rem_euclid(self.num_rows))
+    }
+
+    // Position one step away in the given direction, wrapping around the edges
+    pub fn next_pos(&self, pos: (i32, i32), direction: Direction) -> (i32, i32) {
+        let (x, y) = pos;
+        let next = match direction {
+            Direction::Up => (x, y - 1),
+            Direction::Down => (x, y + 1),
+            Direction::Left => (x - 1, y),
+            Direction::Right => (x + 1, y),
+        };
+        self.wrap(next)
+    }
+
+    // The four tiles around pos in the arcade order (up, left, down, right), wrapping around the edges
+    pub fn neighbors(&self, pos: (i32, i32)) -> [(i32, i32); 4] {
+        [Direction::Up, Direction::Left, Direction::Down, Direction::Right].map(|direction| self.next_pos(pos, direction))
+    }
+
+    // Neighbours the agent may step on
+    pub fn walkable_neighbors(&self, pos: (i32, i32), walkability: Walkability) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.neighbors(pos)
+            .into_iter()
+            .filter(move |&next| next != pos && self.get_tile(next).is_some_and(|tile| walkability.allows(tile)))
     }
 
     pub fn get_tiles(&self) -> &Array2<Tile> {
@@ -114,3 +533,128 @@ impl Grid {
         (self.num_rows * self.tiles[(0, 0)].size.1) as f64
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_file;
+
+    fn load(name: &str, map: &str) -> Result<Grid, MapError> {
+        Grid::new(&test_file(name, map), 16)
+    }
+
+    #[test]
+    fn takes_its_size_from_the_map() {
+        let grid = load("grid-size.map", "#######\n#P..o.#\n#######\n").unwrap();
+        assert_eq!((grid.width(), grid.height()), (7, 3));
+        assert_eq!(grid.get_tile((4, 1)).map(|tile| tile.type_.clone()), Some(TileType::PowerPellet));
+    }
+
+    #[test]
+    fn reads_windows_line_endings() {
+        let grid = load("grid-crlf.map", "#####\r\n#P.o#\r\n#####\r\n\r\n\r\n T\r\n").unwrap();
+        assert_eq!((grid.width(), grid.height()), (5, 3));
+        assert!(grid.is_tunnel((1, 1)));
+        assert_eq!(grid.pellets_left(), 2);
+    }
+
+    #[test]
+    fn reads_the_markers() {
+        let grid = load("grid-markers.map", "b###k\n#PBK#\n#H=F#\n#CIo#\ni###c\n").unwrap();
+        let markers = grid.markers();
+        assert_eq!(markers.pacman_spawn, Some((1, 1)));
+        assert_eq!((markers.ghost_spawn('B'), markers.ghost_spawn('I')), (Some((2, 1)), Some((2, 3))));
+        assert_eq!((markers.scatter_corner('K'), markers.scatter_corner('C')), (Some((4, 0)), Some((4, 4))));
+        assert_eq!((markers.ghost_house, markers.fruit_spot), (Some((1, 2)), Some((3, 2))));
+        // Corners stay walls, the other markers are floor
+        assert_eq!(grid.get_tile((0, 0)).map(|tile| tile.type_.clone()), Some(TileType::Wall));
+        assert_eq!(grid.get_tile((1, 1)).map(|tile| tile.type_.clone()), Some(TileType::Floor));
+    }
+
+    #[test]
+    fn wraps_around_the_edges() {
+        let grid = load("grid-wrap.map", "#####\n P   \n#####\n").unwrap();
+        assert_eq!(grid.next_pos((0, 1), Direction::Left), (4, 1));
+        assert_eq!(grid.next_pos((4, 1), Direction::Right), (0, 1));
+        assert_eq!(grid.next_pos((2, 2), Direction::Down), (2, 0));
+        assert!(grid.in_bounds((4, 2)) && !grid.in_bounds((5, 1)));
+    }
+
+    #[test]
+    fn neighbours_go_through_the_tunnels() {
+        let grid = load("grid-neighbours.map", "#####\n P.. \n#####\n").unwrap();
+        assert_eq!(grid.neighbors((0, 1)), [(0, 0), (4, 1), (0, 2), (1, 1)]);
+        let walkable: Vec<(i32, i32)> = grid.walkable_neighbors((0, 1), Walkability::Pacman).collect();
+        assert_eq!(walkable, vec![(4, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn measures_maze_distances_through_the_tunnels() {
+        let grid = load("grid-distances.map", "#####\n P.. \n#####\n").unwrap();
+        assert_eq!(grid.maze_distance((0, 1), (4, 1)), Some(1));
+        assert_eq!(grid.maze_distance((1, 1), (3, 1)), Some(2));
+        assert_eq!(grid.maze_distance((0, 0), (1, 1)), None); // Wall
+        assert_eq!(grid.maze_distance((9, 9), (1, 1)), None); // Outside the map
+    }
+
+    #[test]
+    fn the_gate_only_opens_to_eaten_ghosts() {
+        let gate = Tile::new((0, 0), (16, 16), TileType::GhostGate);
+        assert!(!Walkability::Pacman.allows(&gate));
+        assert!(!Walkability::Ghost.allows(&gate));
+        assert!(Walkability::EatenGhost.allows(&gate));
+    }
+
+    #[test]
+    fn reads_the_zone_layer() {
+        let grid = load("grid-zones.map", "#####\n P.. \n#.#.#\n#...#\n#####\n\n\nT   T\n R\n").unwrap();
+        assert!(grid.is_tunnel((0, 1)) && grid.is_tunnel((4, 1)));
+        assert!(!grid.is_tunnel((2, 1)));
+        assert!(grid.is_red_zone((1, 2)));
+        assert_eq!(grid.zone((3, 3)), Zone::None);
+        assert_eq!(grid.zone((-1, 0)), Zone::None);
+    }
+
+    #[test]
+    fn reports_map_errors() {
+        let error = |name: &str, map: &str| load(name, map).err().expect("the map should not load");
+
+        assert!(matches!(error("grid-empty.map", ""), MapError::Empty));
+        assert!(matches!(
+            error("grid-ragged.map", "#####\n#P.#\n#####\n"),
+            MapError::RaggedRow { line: 2, expected: 5, found: 4 }
+        ));
+        assert!(matches!(
+            error("grid-glyph.map", "#####\n#P.x#\n#####\n"),
+            MapError::UnknownGlyph { line: 2, column: 4, glyph: 'x' }
+        ));
+        assert!(matches!(error("grid-no-spawn.map", "#####\n#...#\n#####\n"), MapError::MissingPacmanSpawn));
+        assert!(matches!(
+            error("grid-zone.map", "###\n#P#\n###\n\n\n\n\nT\n"),
+            MapError::ZoneOutOfBounds { line: 8 }
+        ));
+        match error("grid-unreachable.map", "######\n#P#..#\n######\n") {
+            MapError::UnreachablePellets(positions) => assert_eq!(positions, vec![(3, 1), (4, 1)]),
+            other => panic!("unexpected error {:?}", other),
+        }
+        assert!(matches!(Grid::new("no-such-file.map", 16), Err(MapError::MissingFile { .. })));
+
+        // Not UTF-8 on the second line
+        let path = test_file("grid-binary.map", "");
+        std::fs::write(&path, b"#####\n#P\xff.#\n#####\n").unwrap();
+        assert!(matches!(Grid::new(&path, 16), Err(MapError::Io { line: 2, .. })));
+    }
+
+    #[test]
+    fn eats_and_resets_pellets() {
+        let mut grid = load("grid-pellets.map", "#####\n#P.o#\n#####\n").unwrap();
+        assert_eq!(grid.eat_pellet((2, 1)), Some(TileType::Pellet));
+        assert_eq!(grid.eat_pellet((2, 1)), None);
+        assert_eq!(grid.eat_pellet((1, 1)), None); // Floor
+        assert_eq!((grid.pellets_left(), grid.pellets_eaten()), (1, 1));
+
+        grid.reset_pellets();
+        assert!(grid.has_pellet((2, 1)));
+        assert_eq!(grid.pellets_left(), 2);
+    }
+}
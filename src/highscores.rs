@@ -0,0 +1,242 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::constants::{HIGH_SCORE_ENTRIES, HIGH_SCORE_FILE};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HighScore {
+    pub initials: String,
+    pub score: i32,
+}
+
+// Best scores, highest first.
+//
+// File format (plain text, one score per line):
+//   AAA 12340
+//   YAS 9870
+#[derive(Clone, Debug, Default)]
+pub struct HighScoreTable {
+    entries: Vec<HighScore>,
+}
+
+// Where the table is kept: the user's data directory, the current directory when there is none
+pub fn default_path() -> PathBuf {
+    let data_dir = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+
+    match data_dir {
+        Some(dir) => dir.join("pacman-rs").join(HIGH_SCORE_FILE),
+        None => PathBuf::from(HIGH_SCORE_FILE),
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// Moves a table that could not be loaded out of the way, so that saving a new one does not
+// destroy the old scores. Returns where it went
+pub fn set_aside(path: &Path) -> io::Result<PathBuf> {
+    let bad_path = with_suffix(path, ".bad");
+    fs::rename(path, &bad_path)?;
+    Ok(bad_path)
+}
+
+impl HighScoreTable {
+    // A missing file is an empty table, the first game creates it
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HighScoreTable::default()),
+            Err(e) => return Err(e),
+        };
+
+        let mut table = HighScoreTable::default();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry = line
+                .trim()
+                .split_once(' ')
+                .and_then(|(initials, score)| Some((initials, score.trim().parse::<i32>().ok()?)));
+            match entry {
+                Some((initials, score)) => table.insert(initials, score),
+                None => {
+                    let message = format!("high score line {}: expected `<initials> <score>`", index + 1);
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+                }
+            };
+        }
+
+        Ok(table)
+    }
+
+    // Written next to the table then renamed over it, a crash while writing leaves the old table intact
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+
+        let temp_path = with_suffix(path, ".tmp");
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        for entry in &self.entries {
+            writeln!(writer, "{} {}", entry.initials, entry.score)?;
+        }
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        fs::rename(&temp_path, path)
+    }
+
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+
+    pub fn best(&self) -> Option<i32> {
+        self.entries.first().map(|entry| entry.score)
+    }
+
+    // Whether the score makes it into the table
+    pub fn qualifies(&self, score: i32) -> bool {
+        score > 0 && (self.entries.len() < HIGH_SCORE_ENTRIES || self.entries.last().is_some_and(|last| score > last.score))
+    }
+
+    // Adds a score below the equal ones already there, returns its rank if it stays in the table
+    pub fn insert(&mut self, initials: &str, score: i32) -> Option<usize> {
+        let rank = self.entries.iter().position(|entry| score > entry.score).unwrap_or(self.entries.len());
+        self.entries.insert(rank, HighScore { initials: initials.to_string(), score });
+        self.entries.truncate(HIGH_SCORE_ENTRIES);
+        (rank < HIGH_SCORE_ENTRIES).then_some(rank)
+    }
+}
+
+// Arcade style three letters entry: up and down change the letter, left and right move between them
+#[derive(Clone, Debug)]
+pub struct InitialsEntry {
+    pub score: i32,
+    letters: [u8; 3],
+    cursor: usize,
+}
+
+impl InitialsEntry {
+    pub fn new(score: i32) -> Self {
+        InitialsEntry { score, letters: [b'A'; 3], cursor: 0 }
+    }
+
+    pub fn initials(&self) -> String {
+        self.letters.iter().map(|&letter| letter as char).collect()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn next_letter(&mut self) {
+        let letter = &mut self.letters[self.cursor];
+        *letter = if *letter == b'Z' { b'A' } else { *letter + 1 };
+    }
+
+    pub fn previous_letter(&mut self) {
+        let letter = &mut self.letters[self.cursor];
+        *letter = if *letter == b'A' { b'Z' } else { *letter - 1 };
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.letters.len() - 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{load_error, test_file};
+
+    #[test]
+    fn keeps_the_best_scores_in_order() {
+        let mut table = HighScoreTable::default();
+        assert!(!table.qualifies(0));
+        assert_eq!(table.insert("AAA", 100), Some(0));
+        assert_eq!(table.insert("BBB", 300), Some(0));
+        assert_eq!(table.insert("CCC", 100), Some(2)); // Below the equal score already there
+
+        let initials: Vec<&str> = table.entries().iter().map(|entry| entry.initials.as_str()).collect();
+        assert_eq!(initials, ["BBB", "AAA", "CCC"]);
+        assert_eq!(table.best(), Some(300));
+    }
+
+    #[test]
+    fn a_full_table_only_takes_better_scores() {
+        let mut table = HighScoreTable::default();
+        for score in 1..=HIGH_SCORE_ENTRIES as i32 {
+            table.insert("AAA", score * 10);
+        }
+
+        assert!(!table.qualifies(10));
+        assert!(table.qualifies(11));
+        assert_eq!(table.insert("LOW", 5), None);
+        assert_eq!(table.insert("NEW", 55), Some(5));
+        assert_eq!(table.entries().len(), HIGH_SCORE_ENTRIES);
+        assert_eq!(table.entries().last().map(|entry| entry.score), Some(20));
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let path = PathBuf::from(test_file("highscores-round-trip.txt", ""));
+        let mut table = HighScoreTable::default();
+        table.insert("YAS", 9870);
+        table.insert("AAA", 12340);
+        table.save(&path).unwrap();
+
+        let loaded = HighScoreTable::load(&path).unwrap();
+        assert_eq!(loaded.entries(), table.entries());
+        assert!(HighScoreTable::load(Path::new("no-such-highscores.txt")).unwrap().entries().is_empty());
+    }
+
+    #[test]
+    fn rejects_a_corrupt_table_with_the_line() {
+        let load = |path: &str| HighScoreTable::load(Path::new(path));
+        assert_eq!(
+            load_error("highscores-corrupt.txt", "AAA 100\nnot a score\n", load),
+            "high score line 2: expected `<initials> <score>`"
+        );
+    }
+
+    #[test]
+    fn a_corrupt_table_is_set_aside() {
+        let path = PathBuf::from(test_file("highscores-set-aside.txt", "AAA 100\nnot a score\n"));
+        assert!(HighScoreTable::load(&path).is_err());
+
+        let bad_path = set_aside(&path).unwrap();
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(bad_path).unwrap(), "AAA 100\nnot a score\n");
+    }
+
+    #[test]
+    fn initials_wrap_around_the_alphabet() {
+        let mut entry = InitialsEntry::new(100);
+        entry.previous_letter();
+        entry.move_right();
+        entry.next_letter();
+        entry.move_right();
+        entry.move_right();
+        entry.next_letter();
+        assert_eq!(entry.initials(), "ZBB");
+        assert_eq!(entry.cursor(), 2);
+    }
+}
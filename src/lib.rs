@@ -0,0 +1,15 @@
+pub mod analysis;
+pub mod bot;
+pub mod brain;
+pub mod constants;
+pub mod enums;
+pub mod game;
+pub mod ghost;
+pub mod grid;
+pub mod highscores;
+pub mod player;
+pub mod utils;
+pub mod pathfinding;
+pub mod replay;
+pub mod roster;
+pub mod rules;
@@ -1,24 +1,138 @@
-mod constants;
-mod enums;
-mod game;
-mod ghost;
-mod grid;
-mod player;
-mod utils; 
-mod pathfinding;
-
-use game::Game;
+mod renderer;
+
+use pacman_rs::analysis::analyze_map;
+use pacman_rs::bot;
+use pacman_rs::game::{Game, GameState};
+use pacman_rs::grid::Grid;
+use pacman_rs::highscores::{self, HighScoreTable, InitialsEntry};
+use pacman_rs::enums::{Direction, Input};
+use pacman_rs::replay::Replay;
+use pacman_rs::roster::Roster;
+use pacman_rs::rules::Ruleset;
 use piston_window::*;
+use renderer::Renderer;
+use std::process;
 
-use constants::{
-    GRID_WIDTH,
-    GRID_HEIGHT,
+use pacman_rs::constants::{
     CELL_SIZE,
+    DEFAULT_REPLAY_FILE,
+    DEMO_DURATION,
+    GAME_OVER_SCREEN_TIME,
+    MAP_FILE,
+    ROSTER_FILE,
+    RULES_FILE,
+    TITLE_IDLE_TIME,
 };
 
+// What the window shows, the game itself only knows about the game being played
+#[derive(Clone, Copy, PartialEq)]
+enum Screen {
+    Title,    // Ghost introductions and high score, waiting for Enter
+    Demo,     // Attract mode: the bot plays until a key is pressed
+    Playing,
+    GameOver, // Initials entry when the score made it, then the high scores
+}
+
+fn key_to_direction(button: &Button) -> Option<Direction> {
+    match *button {
+        Button::Keyboard(Key::Left) => Some(Direction::Left),
+        Button::Keyboard(Key::Up) => Some(Direction::Up),
+        Button::Keyboard(Key::Right) => Some(Direction::Right),
+        Button::Keyboard(Key::Down) => Some(Direction::Down),
+        _ => None,
+    }
+}
+
+// Value following a `--flag` on the command line
+fn arg_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .cloned()
+}
+
+fn load_roster(path: &str) -> Roster {
+    Roster::load(path).unwrap_or_else(|e| {
+        eprintln!("Failed to load ghost roster {}: {}", path, e);
+        process::exit(1);
+    })
+}
+
+fn load_rules(path: &str) -> Ruleset {
+    Ruleset::load(path).unwrap_or_else(|e| {
+        eprintln!("Failed to load rules {}: {}", path, e);
+        process::exit(1);
+    })
+}
+
+// `check-map <file>`: print the map analysis, exit code 1 when the map is broken
+fn check_map(path: &str) -> i32 {
+    let grid = match Grid::load(path, CELL_SIZE) {
+        Ok(grid) => grid,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            return 1;
+        }
+    };
+
+    let report = analyze_map(&grid);
+    print!("{}", report);
+
+    if report.has_errors() { 1 } else { 0 }
+}
+
+// A fresh game with a random seed, the map already loaded once so it cannot fail anymore
+fn new_game(map_path: &str, roster: &Roster, rules: &Ruleset) -> Game {
+    let seed: u64 = rand::random();
+    Game::new(map_path, roster.clone(), rules.clone(), seed).unwrap_or_else(|e| {
+        eprintln!("Failed to start the game: {}", e);
+        process::exit(1);
+    })
+}
+
+fn save_replay(game: &Game, path: &str) {
+    match game.to_replay().save(path) {
+        Ok(()) => println!("Replay saved to {}", path),
+        Err(e) => eprintln!("Failed to save replay {}: {}", path, e),
+    }
+}
+
 fn main() {
-    // Create a Glutin window.
-    let mut window: PistonWindow = WindowSettings::new("Pacman RS By Yasuke", [(GRID_WIDTH * CELL_SIZE) as f64 + 100., (GRID_HEIGHT * CELL_SIZE) as f64])
+    let args: Vec<String> = std::env::args().collect();
+
+    if args.get(1).map(String::as_str) == Some("check-map") {
+        let path = args.get(2).map(String::as_str).unwrap_or(MAP_FILE);
+        process::exit(check_map(path));
+    }
+
+    let replay_path = arg_value(&args, "--replay");
+    let record_path = arg_value(&args, "--record").unwrap_or_else(|| DEFAULT_REPLAY_FILE.to_string());
+    let map_path = arg_value(&args, "--map").unwrap_or_else(|| MAP_FILE.to_string());
+    let roster = load_roster(&arg_value(&args, "--roster").unwrap_or_else(|| ROSTER_FILE.to_string()));
+    let rules = load_rules(&arg_value(&args, "--rules").unwrap_or_else(|| RULES_FILE.to_string()));
+
+    // A replay is played right away, otherwise the game behind the title screen only sizes the window
+    let loaded = match &replay_path {
+        Some(path) => {
+            let replay = Replay::load(path).unwrap_or_else(|e| {
+                eprintln!("Failed to load replay {}: {}", path, e);
+                process::exit(1);
+            });
+            println!("Replaying {} (seed {}, {} inputs)", path, replay.seed, replay.inputs.len());
+            Game::from_replay(&replay, load_roster(&replay.roster), load_rules(&replay.rules))
+        },
+        None => Game::new(&map_path, roster.clone(), rules.clone(), 0),
+    };
+    let mut game = loaded.unwrap_or_else(|e| {
+        eprintln!("Failed to start the game: {}", e);
+        process::exit(1);
+    });
+    let mut replaying = replay_path.is_some();
+    let mut screen = if replaying { Screen::Playing } else { Screen::Title };
+    let mut screen_timer = 0.0; // Seconds spent on the current screen
+
+    // Create a Glutin window sized after the loaded map.
+    let mut window: PistonWindow = WindowSettings::new("Pacman RS By Yasuke", [game.grid().get_dim_width() + 100., game.grid().get_dim_height()])
                                 .exit_on_esc(true)
                                 .vsync(true)
                                 .build()
@@ -27,21 +141,146 @@ fn main() {
     let assets = find_folder::Search::Parents(1)
             .for_folder("assets").unwrap();
     let glyphs = window.load_font(assets.join("ARCADE_N.TTF")).unwrap();
-    // Create a new game and run it.
-    let mut game = Game::new(glyphs);
-    
+    // Create the renderer drawing the game.
+    let mut renderer = Renderer::new(glyphs);
+
+    let high_score_path = highscores::default_path();
+    // A table that cannot be read is moved aside before the first save, or never overwritten
+    let (mut high_scores, can_save_high_scores) = match HighScoreTable::load(&high_score_path) {
+        Ok(table) => (table, true),
+        Err(e) => {
+            eprintln!("Failed to load high scores {}: {}", high_score_path.display(), e);
+            match highscores::set_aside(&high_score_path) {
+                Ok(bad_path) => {
+                    eprintln!("Moved the unreadable high scores to {}", bad_path.display());
+                    (HighScoreTable::default(), true)
+                },
+                Err(e) => {
+                    eprintln!("Failed to move the high scores aside ({}), new scores will not be saved", e);
+                    (HighScoreTable::default(), false)
+                },
+            }
+        },
+    };
+    let mut initials_entry: Option<InitialsEntry> = None;
+
     while let Some(event) = window.next() {
+        // Nobody is watching an unfocused window, do not let the ghosts play alone
+        if event.focus_args() == Some(false) && screen == Screen::Playing {
+            game.pause();
+        }
+
         if let Some(args) = event.update_args() {
-            game.update(args.dt);
+            screen_timer += args.dt;
+
+            match screen {
+                Screen::Title => {
+                    if screen_timer >= TITLE_IDLE_TIME {
+                        game = new_game(&map_path, &roster, &rules);
+                        screen = Screen::Demo;
+                        screen_timer = 0.0;
+                    }
+                },
+                Screen::Demo => {
+                    if let Some(direction) = bot::choose_direction(&game) {
+                        game.handle_input(Input::Move(direction));
+                    }
+                    game.update(args.dt);
+
+                    if *game.game_state() == GameState::GameOver || screen_timer >= DEMO_DURATION {
+                        screen = Screen::Title;
+                        screen_timer = 0.0;
+                    }
+                },
+                Screen::Playing => {
+                    game.update(args.dt);
+
+                    if *game.game_state() == GameState::GameOver {
+                        // Replays do not go into the high score table
+                        if !replaying {
+                            save_replay(&game, &record_path);
+                            if high_scores.qualifies(game.pacman().score) {
+                                initials_entry = Some(InitialsEntry::new(game.pacman().score));
+                            }
+                        }
+                        screen = Screen::GameOver;
+                        screen_timer = 0.0;
+                    }
+                },
+                Screen::GameOver => {
+                    if initials_entry.is_none() && screen_timer >= GAME_OVER_SCREEN_TIME {
+                        screen = Screen::Title;
+                        screen_timer = 0.0;
+                    }
+                },
+            }
         }
 
-        if let Some(input) = event.press_args() {
-            game.handle_input(&input);
+        if let Some(button) = event.press_args() {
+            match screen {
+                Screen::Title => {
+                    if button == Button::Keyboard(Key::Return) {
+                        game = new_game(&map_path, &roster, &rules);
+                        println!("Game seed: {}", game.seed());
+                        replaying = false;
+                        screen = Screen::Playing;
+                        screen_timer = 0.0;
+                    }
+                },
+                // Any key stops the demo
+                Screen::Demo => {
+                    screen = Screen::Title;
+                    screen_timer = 0.0;
+                },
+                Screen::Playing => {
+                    if button == Button::Keyboard(Key::P) {
+                        // Pausing does not change the run, replays can be paused too
+                        game.handle_input(Input::Pause);
+                    } else if let Some(direction) = key_to_direction(&button) {
+                        // Keyboard is ignored while replaying so the run cannot diverge
+                        if !replaying {
+                            game.handle_input(Input::Move(direction));
+                        }
+                    }
+                },
+                Screen::GameOver => match initials_entry.as_mut() {
+                    Some(entry) => match button {
+                        Button::Keyboard(Key::Up) => entry.next_letter(),
+                        Button::Keyboard(Key::Down) => entry.previous_letter(),
+                        Button::Keyboard(Key::Left) => entry.move_left(),
+                        Button::Keyboard(Key::Right) => entry.move_right(),
+                        Button::Keyboard(Key::Return) => {
+                            high_scores.insert(&entry.initials(), entry.score);
+                            // The old file is left alone when it could not be moved aside
+                            let saved = if can_save_high_scores { high_scores.save(&high_score_path) } else { Ok(()) };
+                            if let Err(e) = saved {
+                                eprintln!("Failed to save high scores {}: {}", high_score_path.display(), e);
+                            }
+                            initials_entry = None;
+                            screen_timer = 0.0; // Leave the table up for a while
+                        },
+                        _ => {}
+                    },
+                    None => {
+                        if button == Button::Keyboard(Key::Return) {
+                            screen = Screen::Title;
+                            screen_timer = 0.0;
+                        }
+                    },
+                },
+            }
         }
 
         // Handle render events
-        window.draw_2d(&event, |context, graphics, device| {
-            game.render(context, graphics, device);
+        window.draw_2d(&event, |context, graphics, device| match screen {
+            Screen::Title => renderer.render_title(&roster, &high_scores, screen_timer, context, graphics, device),
+            Screen::Demo => renderer.render_demo(&game, &high_scores, context, graphics, device),
+            Screen::Playing | Screen::GameOver => renderer.render(&game, &high_scores, initials_entry.as_ref(), context, graphics, device),
         });
     }
+
+    // Quitting in the middle of a game still records it
+    if screen == Screen::Playing && !replaying {
+        save_replay(&game, &record_path);
+    }
 }
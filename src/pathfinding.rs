@@ -1,219 +1,206 @@
-use std::vec;
-
-use crate::constants::{GRID_HEIGHT, GRID_WIDTH};
-use crate::grid::Grid; // Add Grid and TileType imports
-use crate::utils::{find_neighbors, manhattan_distance};
-use ndarray::Array2;
-struct Node {
-    // Define the structure of your node here
-    g: f32,
-    h: f32,
-    f: f32,
-    pos: (i32, i32),
-    neighbors: Vec<(i32, i32)>,
-}
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 
-impl Node {
-    pub fn new(pos: (i32, i32)) -> Self {
-        Node {
-            g: 0.0,
-            h: 0.0,
-            f: 0.0,
-            pos,
-            neighbors: find_neighbors(pos),
-        }
-    }
-}
+use crate::grid::{Grid, Walkability};
 
+// A* over the maze tiles, built once per grid and reused for every search:
+// all per-cell state lives in dense arrays indexed by `row * width + col`, and a
+// search stamp marks which entries belong to the current search so nothing has to
+// be cleared between calls.
 pub struct AStar {
-    grid: Array2<Node>,
-    open_list: Vec<(i32, i32)>,
-    closed_list: Vec<(i32, i32)>,
-    current_goal: Option<(i32, i32)>, // Current target position
+    width: i32,
+    height: i32,
+    neighbors: Vec<[usize; 4]>, // Up, left, down and right of each cell, through the tunnels
+    g: Vec<u32>,
+    parent: Vec<usize>,
+    seen: Vec<u32>,   // Search stamp of the last search that reached the cell
+    closed: Vec<u32>, // Search stamp of the last search that expanded the cell
+    search: u32,
+    open: BinaryHeap<Reverse<(u32, u32, usize)>>, // (f, h, cell), lowest f then lowest h first
 }
 
 impl AStar {
-    pub fn new() -> Self {
-        let grid: Array2<Node> =
-            Array2::from_shape_fn((GRID_WIDTH as usize, GRID_HEIGHT as usize), |(col, row)| {
-                Node::new((col as i32, row as i32))
-            });
+    pub fn new(game_grid: &Grid) -> Self {
+        let (width, height) = (game_grid.width(), game_grid.height());
+        let cells = (width * height) as usize;
+
+        let neighbors = (0..cells)
+            .map(|index| {
+                let pos = (index as i32 % width, index as i32 / width);
+                game_grid.neighbors(pos).map(|(col, row)| (row * width + col) as usize)
+            })
+            .collect();
 
         AStar {
-            open_list: Vec::new(),
-            closed_list: Vec::new(),
-            grid,
-            current_goal: None,
+            width,
+            height,
+            neighbors,
+            g: vec![0; cells],
+            parent: vec![0; cells],
+            seen: vec![0; cells],
+            closed: vec![0; cells],
+            search: 0,
+            open: BinaryHeap::new(),
         }
     }
 
-    pub fn set_goal(&mut self, goal: (i32, i32)) {
-        self.current_goal = Some(goal);
-
-        // Update heuristics for all nodes in the open list
-        let snap_open_positions: Vec<(i32, i32)> = self.open_list.clone();
-        for pos in snap_open_positions.iter() {
-            self.grid
-                .iter_mut()
-                .find(|node| node.pos == *pos)
-                .map(|node: &mut Node| {
-                    if let Some(current_goal) = self.current_goal {
-                        node.h = manhattan_distance(node.pos, current_goal);
-                        node.f = node.g + node.h; // Update f score
-                    }
-                });
-        }
+    fn index(&self, pos: (i32, i32)) -> Option<usize> {
+        let in_bounds = pos.0 >= 0 && pos.0 < self.width && pos.1 >= 0 && pos.1 < self.height;
+        in_bounds.then(|| (pos.1 * self.width + pos.0) as usize)
     }
 
-    fn get_node_mut(&mut self, pos: (i32, i32)) -> Option<&mut Node> {
-        if pos.0 >= 0 && pos.1 >= 0 && pos.0 < GRID_WIDTH && pos.1 < GRID_HEIGHT {
-            Some(&mut self.grid[(pos.0 as usize, pos.1 as usize)])
-        } else {
-            None
-        }
+    fn pos(&self, index: usize) -> (i32, i32) {
+        (index as i32 % self.width, index as i32 / self.width)
     }
 
-    fn get_node(&self, pos: (i32, i32)) -> Option<&Node> {
-        if pos.0 >= 0 && pos.1 >= 0 && pos.0 < GRID_WIDTH && pos.1 < GRID_HEIGHT {
-            Some(&self.grid[(pos.0 as usize, pos.1 as usize)])
-        } else {
-            None
+    // Manhattan distance taking the shorter way around the edges, so that it never
+    // overestimates a path going through a tunnel
+    fn heuristic(&self, a: usize, b: usize) -> u32 {
+        let (a, b) = (self.pos(a), self.pos(b));
+        let dx = (a.0 - b.0).abs();
+        let dy = (a.1 - b.1).abs();
+        (dx.min(self.width - dx) + dy.min(self.height - dy)) as u32
+    }
+
+    // Start a new search, the stamps only need a reset when the counter wraps around
+    fn begin_search(&mut self) {
+        self.open.clear();
+        self.search = self.search.wrapping_add(1);
+        if self.search == 0 {
+            self.seen.fill(0);
+            self.closed.fill(0);
+            self.search = 1;
         }
     }
 
+    // Shortest path from start to goal, both included, for an agent walking on the tiles the
+    // walkability allows. None if the goal cannot be reached
     pub fn find_path(
         &mut self,
         start: (i32, i32),
-        initial_goal: (i32, i32),
+        goal: (i32, i32),
         game_grid: &Grid,
+        walkability: Walkability,
     ) -> Option<Vec<(i32, i32)>> {
-        // Check if the start position is valid
-        if !game_grid.get_tile(start).unwrap().is_walkable_for_ghost() {
-            return None; // Start position is not walkable
+        let walkable = |pos: (i32, i32)| game_grid.get_tile(pos).is_some_and(|tile| walkability.allows(tile));
+        if !walkable(start) || !walkable(goal) {
+            return None;
         }
-        // Check if the initial goal is valid
-        if !game_grid
-            .get_tile(initial_goal)
-            .unwrap()
-            .is_walkable_for_ghost()
-        {
-            return None; // Initial goal position is not walkable
-        }
-        // Check if the start and goal are the same
-        if start == initial_goal {
+        if start == goal {
             return Some(vec![start]); // No path needed, already at goal
         }
 
-        // Reset the pathfinding state
-        self.reset_path();
-        // Initialize the start node
-        self.open_list.push(start);
-        // Set the initial goal
-        self.set_goal(initial_goal);
-
-        // Keep a separate data structure for parent tracking
-        let mut came_from: std::collections::HashMap<(i32, i32), (i32, i32)> =
-            std::collections::HashMap::new();
-
-        while !self.open_list.is_empty() {
-            let snapshot_open_list = self.open_list.clone();
-            // Find the node with lowest f score
-            let (min_index, _) = snapshot_open_list
-                .iter()
-                .enumerate()
-                .min_by(|&(_, &a), &(_, &b)| {
-                    let a_node = self.get_node(a).unwrap();
-                    let b_node = self.get_node(b).unwrap();
-                    a_node.f.partial_cmp(&b_node.f).unwrap()
-                })
-                .unwrap();
-
-            let current = self.open_list.remove(min_index);
-            // Check if we reached the goal (which may have changed)
-            let goal: (i32, i32) = self.current_goal.unwrap();
+        let (start, goal) = (self.index(start)?, self.index(goal)?);
+        self.begin_search();
+
+        let search = self.search;
+        self.g[start] = 0;
+        self.seen[start] = search;
+        let h = self.heuristic(start, goal);
+        self.open.push(Reverse((h, h, start)));
+
+        while let Some(Reverse((_, _, current))) = self.open.pop() {
+            // Stale entries are left in the heap when a better path is found, skip them
+            if self.closed[current] == search {
+                continue;
+            }
             if current == goal {
-                // Reconstruct the path
-                return Some(reconstruct_path(&came_from, start, goal));
+                return Some(self.reconstruct_path(start, goal));
             }
+            self.closed[current] = search;
 
-            // Add to closed list
-            self.closed_list.push(current);
-
-            // Get neighbors one at a time and process them
-            let neighbor_positions = {
-                let current_node = self.get_node(current).unwrap();
-                current_node.neighbors.clone()
-            };
-
-            for neighbor_pos in neighbor_positions {
-                // Skip if in closed list or not walkable
-                if self.closed_list.contains(&neighbor_pos)
-                    || !game_grid
-                        .get_tile(neighbor_pos)
-                        .unwrap()
-                        .is_walkable_for_ghost()
-                {
+            let tentative_g = self.g[current] + 1; // Every move costs the same
+            for neighbor in self.neighbors[current] {
+                if self.closed[neighbor] == search || !walkable(self.pos(neighbor)) {
                     continue;
                 }
 
-                // Calculate the tentative g score
-                let tentative_g = {
-                    let current_node = self.get_node(current).unwrap();
-                    current_node.g + 1.0 // Using uniform cost for simplicity
-                };
-
-                let is_in_open_list = self.open_list.contains(&neighbor_pos);
-                let is_better = {
-                    if let Some(neighbor_node) = self.get_node(neighbor_pos) {
-                        !is_in_open_list || tentative_g < neighbor_node.g
-                    } else {
-                        false
-                    }
-                };
-
-                if is_better {
-                    // Update the neighbor with the current goal
-                    let goal = self.current_goal.unwrap();
-                    {
-                        let neighbor_node: &mut Node = self.get_node_mut(neighbor_pos).unwrap();
-                        neighbor_node.g = tentative_g;
-                        neighbor_node.h = manhattan_distance(neighbor_pos, goal);
-                        neighbor_node.f = neighbor_node.g + neighbor_node.h;
-                    }
-
-                    // Record where this node came from for path reconstruction
-                    came_from.insert(neighbor_pos, current);
-
-                    // Add to open list if not there
-                    if !self.open_list.contains(&neighbor_pos) {
-                        self.open_list.push(neighbor_pos);
-                    }
+                if self.seen[neighbor] != search || tentative_g < self.g[neighbor] {
+                    self.seen[neighbor] = search;
+                    self.g[neighbor] = tentative_g;
+                    self.parent[neighbor] = current;
+
+                    let h = self.heuristic(neighbor, goal);
+                    self.open.push(Reverse((tentative_g + h, h, neighbor)));
                 }
             }
         }
+
         None // No path found
     }
 
-    fn reset_path(&mut self) {
-        self.open_list.clear();
-        self.closed_list.clear();
+    fn reconstruct_path(&self, start: usize, goal: usize) -> Vec<(i32, i32)> {
+        let mut path = Vec::with_capacity(self.g[goal] as usize + 1);
+        let mut current = goal;
+
+        while current != start {
+            path.push(self.pos(current));
+            current = self.parent[current];
+        }
+        path.push(self.pos(start));
+        path.reverse();
+        path
     }
 }
 
-// Helper function to reconstruct path
-fn reconstruct_path(
-    came_from: &std::collections::HashMap<(i32, i32), (i32, i32)>,
-    start: (i32, i32),
-    goal: (i32, i32),
-) -> Vec<(i32, i32)> {
-    let mut path = Vec::new();
-    let mut current = goal;
-
-    while current != start {
-        path.push(current);
-        current = *came_from.get(&current).unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{CELL_SIZE, MAP_FILE};
+
+    #[test]
+    fn finds_paths_and_reuses_its_buffers() {
+        let grid = Grid::new(MAP_FILE, CELL_SIZE).unwrap();
+        let mut astar = AStar::new(&grid);
+        let pacman = grid.markers().pacman_spawn.unwrap();
+        let house = grid.markers().ghost_house.unwrap();
+
+        let path = astar.find_path(pacman, house, &grid, Walkability::EatenGhost).unwrap();
+        assert_eq!((path[0], path[path.len() - 1]), (pacman, house));
+        for step in path.windows(2) {
+            assert!(grid.neighbors(step[0]).contains(&step[1]), "{:?} is not next to {:?}", step[0], step[1]);
+        }
+
+        // Searches in between, or a wrapping stamp, leave no trace
+        astar.find_path(house, (1, 1), &grid, Walkability::EatenGhost);
+        assert_eq!(astar.find_path(pacman, house, &grid, Walkability::EatenGhost), Some(path.clone()));
+        astar.search = u32::MAX;
+        assert_eq!(astar.find_path(pacman, house, &grid, Walkability::EatenGhost), Some(path));
+
+        assert_eq!(astar.find_path(pacman, pacman, &grid, Walkability::Pacman), Some(vec![pacman]));
+    }
+
+    #[test]
+    fn paths_are_as_short_as_the_maze_distance() {
+        let grid = Grid::new(MAP_FILE, CELL_SIZE).unwrap();
+        let mut astar = AStar::new(&grid);
+        let walkable: Vec<(i32, i32)> = grid
+            .get_tiles()
+            .iter()
+            .filter(|tile| Walkability::EatenGhost.allows(tile))
+            .map(|tile| tile.pos)
+            .collect();
+
+        // A spread of pairs, tunnel and ghost house included
+        for &start in walkable.iter().step_by(7) {
+            for &goal in walkable.iter().step_by(13) {
+                // The floor outside the maze walls is walkable but cut off, neither finds a way there
+                let path = astar.find_path(start, goal, &grid, Walkability::EatenGhost);
+                let length = path.as_ref().map(|path| path.len() as u32 - 1);
+                assert_eq!(length, grid.maze_distance(start, goal), "{:?} to {:?}", start, goal);
+            }
+        }
+    }
+
+    #[test]
+    fn walls_and_closed_gates_have_no_path() {
+        let grid = Grid::new(MAP_FILE, CELL_SIZE).unwrap();
+        let mut astar = AStar::new(&grid);
+        let house = grid.markers().ghost_house.unwrap();
+        let pacman = grid.markers().pacman_spawn.unwrap();
+
+        assert!(astar.find_path(pacman, (0, 0), &grid, Walkability::Pacman).is_none());
+        assert!(astar.find_path(pacman, house, &grid, Walkability::Ghost).is_none());
+        assert!(astar.find_path(pacman, house, &grid, Walkability::EatenGhost).is_some());
     }
-    path.push(start);
-    path.reverse();
-    path
 }
@@ -0,0 +1,280 @@
+use piston_window::ellipse::circle;
+use piston_window::*;
+use pacman_rs::game::{Game, GameState};
+use pacman_rs::ghost::Ghost;
+use pacman_rs::grid::TileType;
+use pacman_rs::highscores::{HighScoreTable, InitialsEntry};
+use pacman_rs::enums::{Fruit, GhostState};
+use pacman_rs::roster::Roster;
+
+use pacman_rs::constants::{
+    APPLE_COLOR, BELL_COLOR, CHERRY_COLOR, GALAXIAN_COLOR, KEY_COLOR, MELON_COLOR, ORANGE_COLOR, STRAWBERRY_COLOR,
+    CELL_SIZE, GHOST_EATEN_COLOR, GHOST_FRIGHTENED_COLOR, GHOST_GATE_COLOR, PACMAN_COLOR, PELLET_COLOR, POWER_PELLET_COLOR, WALL_COLOR,
+    GHOST_INTRO_INTERVAL
+};
+
+fn get_color_from_state(ghost: &Ghost) -> [f32; 4] {
+    match ghost.state {
+        GhostState::Frightened => GHOST_FRIGHTENED_COLOR,
+        GhostState::Eaten => GHOST_EATEN_COLOR, // Eaten ghosts are also shown as frightened
+        _ => ghost.color, // Colour from the ghost roster
+    }
+}
+
+fn get_fruit_color(fruit: Fruit) -> [f32; 4] {
+    match fruit {
+        Fruit::Cherry => CHERRY_COLOR,
+        Fruit::Strawberry => STRAWBERRY_COLOR,
+        Fruit::Orange => ORANGE_COLOR,
+        Fruit::Apple => APPLE_COLOR,
+        Fruit::Melon => MELON_COLOR,
+        Fruit::Galaxian => GALAXIAN_COLOR,
+        Fruit::Bell => BELL_COLOR,
+        Fruit::Key => KEY_COLOR,
+    }
+}
+
+// Draws a Game with piston_window, the game itself knows nothing about rendering
+pub struct Renderer {
+    glyphs: Glyphs,
+}
+
+impl Renderer {
+    pub fn new(glyphs: Glyphs) -> Self {
+        Renderer { glyphs }
+    }
+
+    // Game over screen: the initials entry when the score made it into the table, the table otherwise
+    fn render_game_over(&mut self, game: &Game, high_scores: &HighScoreTable, entry: Option<&InitialsEntry>, c: Context, graphics: &mut G2d) {
+        let x = 100.;
+        let mut y = game.grid().get_dim_height() / 3.;
+
+        let mut line = |glyphs: &mut Glyphs, color: [f32; 4], size: u32, text: &str, x: f64, y: f64| {
+            text::Text::new_color(color, size)
+                .draw(text, glyphs, &c.draw_state, c.transform.trans(x, y), graphics)
+                .unwrap();
+        };
+
+        line(&mut self.glyphs, color::RED, 24, "Game Over!", x, y);
+        y += 50.;
+
+        match entry {
+            Some(entry) => {
+                line(&mut self.glyphs, color::WHITE, 12, &format!("New high score: {}", entry.score), x, y);
+                y += 40.;
+                // The letter being edited is highlighted
+                for (i, letter) in entry.initials().chars().enumerate() {
+                    let letter_color = if i == entry.cursor() { PACMAN_COLOR } else { color::WHITE };
+                    line(&mut self.glyphs, letter_color, 24, &letter.to_string(), x + i as f64 * 32., y);
+                }
+                y += 30.;
+                line(&mut self.glyphs, color::WHITE, 8, "Arrows to pick, Enter to confirm", x, y);
+            },
+            None => {
+                line(&mut self.glyphs, color::WHITE, 12, "High Scores", x, y);
+                y += 10.;
+                for (rank, high_score) in high_scores.entries().iter().enumerate() {
+                    y += 20.;
+                    let text = format!("{:>2}. {} {:>7}", rank + 1, high_score.initials, high_score.score);
+                    line(&mut self.glyphs, color::WHITE, 10, &text, x, y);
+                }
+                y += 40.;
+                line(&mut self.glyphs, color::WHITE, 8, "Press Enter", x, y);
+            },
+        }
+    }
+
+    // Title screen: the ghosts of the roster come in one by one, then the press start prompt
+    pub fn render_title(&mut self, roster: &Roster, high_scores: &HighScoreTable, timer: f64, c: Context, graphics: &mut G2d, device: &mut piston_window::GfxDevice) {
+        clear([0.0, 0.0, 0.0, 1.0], graphics);
+
+        let [width, height] = c.get_view_size();
+        let x = 60.;
+        let mut y = 60.;
+
+        let line = |glyphs: &mut Glyphs, graphics: &mut G2d, color: [f32; 4], size: u32, text: &str, x: f64, y: f64| {
+            text::Text::new_color(color, size)
+                .draw(text, glyphs, &c.draw_state, c.transform.trans(x, y), graphics)
+                .unwrap();
+        };
+
+        line(&mut self.glyphs, graphics, color::WHITE, 9, &format!("High Score: {}", high_scores.best().unwrap_or(0)), x, y);
+        y += 60.;
+        line(&mut self.glyphs, graphics, PACMAN_COLOR, 32, "PAC-MAN", width / 2. - 112., y);
+        y += 60.;
+        line(&mut self.glyphs, graphics, color::WHITE, 10, "Character / Nickname", x, y);
+
+        let shown = (timer / GHOST_INTRO_INTERVAL) as usize;
+        for ghost in roster.ghosts.iter().take(shown) {
+            y += 40.;
+            let square = rectangle::square(x, y - CELL_SIZE as f64, CELL_SIZE as f64);
+            rectangle(ghost.color, square, c.transform, graphics);
+            let text = format!("-{}  \"{}\"", ghost.personality.character().to_uppercase(), ghost.name.to_uppercase());
+            line(&mut self.glyphs, graphics, ghost.color, 10, &text, x + 30., y);
+        }
+
+        // Blinks once every ghost is in
+        if shown >= roster.ghosts.len() && timer.fract() < 0.5 {
+            line(&mut self.glyphs, graphics, color::WHITE, 12, "Press Enter to start", x, height - 80.);
+        }
+
+        self.glyphs.factory.encoder.flush(device);
+    }
+
+    // Attract mode: the game played by the bot, with a banner in the HUD
+    pub fn render_demo(&mut self, game: &Game, high_scores: &HighScoreTable, c: Context, graphics: &mut G2d, device: &mut piston_window::GfxDevice) {
+        self.render_game(game, high_scores, None, false, c, graphics);
+
+        text::Text::new_color(color::RED, 12)
+            .draw("DEMO", &mut self.glyphs, &c.draw_state, c.transform.trans(game.grid().get_dim_width(), 250.0), graphics)
+            .unwrap();
+        text::Text::new_color(color::WHITE, 6)
+            .draw("Press a key", &mut self.glyphs, &c.draw_state, c.transform.trans(game.grid().get_dim_width(), 270.0), graphics)
+            .unwrap();
+        self.glyphs.factory.encoder.flush(device);
+    }
+
+    // Dims the maze, with the countdown once the player asked to go on
+    fn render_pause(&mut self, game: &Game, c: Context, graphics: &mut G2d) {
+        let (width, height) = (game.grid().get_dim_width(), game.grid().get_dim_height());
+        rectangle([0.0, 0.0, 0.0, 0.6], [0., 0., width, height], c.transform, graphics);
+
+        let (text, hint) = match game.resume_countdown() {
+            Some(countdown) => (format!("{}", countdown.ceil() as i32), ""),
+            None => ("Paused".to_string(), "Press P to resume"),
+        };
+        text::Text::new_color(PACMAN_COLOR, 24)
+            .draw(&text, &mut self.glyphs, &c.draw_state, c.transform.trans(width / 2. - 12. * text.len() as f64, height / 2.), graphics)
+            .unwrap();
+        text::Text::new_color(color::WHITE, 8)
+            .draw(hint, &mut self.glyphs, &c.draw_state, c.transform.trans(width / 2. - 4. * hint.len() as f64, height / 2. + 30.), graphics)
+            .unwrap();
+    }
+
+    pub fn render(&mut self, game: &Game, high_scores: &HighScoreTable, entry: Option<&InitialsEntry>, c: Context, graphics: &mut G2d, device: &mut piston_window::GfxDevice) {
+        self.render_game(game, high_scores, entry, true, c, graphics);
+        self.glyphs.factory.encoder.flush(device);
+    }
+
+    // The game screen, flushed by the caller. The score only shows as the high score when it
+    // can make it into the table, not in the attract mode
+    fn render_game(&mut self, game: &Game, high_scores: &HighScoreTable, entry: Option<&InitialsEntry>, scores_count: bool, c: Context, graphics: &mut G2d) {
+        const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+        // Clear the screen.
+        clear(BLACK, graphics);
+
+        if *game.game_state() == GameState::GameOver {
+            self.render_game_over(game, high_scores, entry, c, graphics);
+            return;
+        }
+
+        if *game.game_state() == GameState::Restarting {
+            // Center the text
+            let text = if game.has_win() { format!("Go to level {}", game.level()) } else { "You die!".to_string() };
+            text::Text::new_color(color::RED, 24)
+                .draw(text.as_str(), &mut self.glyphs, &c.draw_state, c.transform.trans(100., game.grid().get_dim_height() / 2.), graphics)
+                .unwrap();
+
+            return; // Skip rendering the rest of the game
+        }
+
+        let pacman = game.pacman();
+
+        // Draw the high score, the score and the lives
+        let best = high_scores.best().unwrap_or(0);
+        let high_score = if scores_count { best.max(pacman.score) } else { best };
+        text::Text::new_color(color::WHITE, 9)
+            .draw(&format!("High Score: {}", high_score), &mut self.glyphs, &c.draw_state, c.transform.trans(game.grid().get_dim_width(), 50.0), graphics)
+            .unwrap();
+
+        let score_text = format!("Score: {}", pacman.score);
+        let lives_text = format!("Lives: {}", pacman.lives);
+
+        text::Text::new_color(color::WHITE, 9)
+            .draw(&score_text, &mut self.glyphs, &c.draw_state, c.transform.trans(game.grid().get_dim_width(),100.0), graphics)
+            .unwrap();
+        text::Text::new_color(color::WHITE, 9)
+            .draw(&lives_text, &mut self.glyphs, &c.draw_state, c.transform.trans(game.grid().get_dim_width(), 150.0), graphics)
+            .unwrap();
+
+        // Pre-calculate common values
+        let transform = c.transform;
+
+        // Draw the recent fruits row below the lives
+        for (i, fruit) in game.collected_fruits().iter().enumerate() {
+            let fruit_x = game.grid().get_dim_width() + (i as f64 * CELL_SIZE as f64 * 0.8);
+            let fruit_circle = circle(fruit_x + CELL_SIZE as f64 / 2.0, 180.0, CELL_SIZE as f64 * 0.35);
+            ellipse(get_fruit_color(*fruit), fruit_circle, transform, graphics);
+        }
+        let pellet_size = CELL_SIZE as f64 * 0.2;
+        let power_size = CELL_SIZE as f64 * 0.5;
+
+        // Draw walls first (they don't change)
+        for tile in game.grid().get_tiles() {
+            if tile.type_ == TileType::Wall || tile.type_ == TileType::GhostGate {
+                let tile_x = tile.get_pixels_x() as f64;
+                let tile_y = tile.get_pixels_y() as f64;
+                let tile_size = tile.get_size().0 as f64;
+
+                let square = rectangle::square(tile_x, tile_y, tile_size);
+                let color = match tile.type_ {
+                    TileType::Wall => WALL_COLOR,
+                    TileType::GhostGate => GHOST_GATE_COLOR,
+                    _ => unreachable!(),
+                };
+                rectangle(color, square, transform, graphics);
+            }
+        }
+
+        // Draw pellets (only if not eaten)
+        for tile in game.grid().get_tiles() {
+            // Skip non-pellet tiles and eaten pellets (single check)
+            if !game.grid().has_pellet(tile.pos) {
+                continue;
+            }
+
+            let tile_x = tile.get_pixels_x() as f64;
+            let tile_y = tile.get_pixels_y() as f64;
+            let tile_size = tile.get_size().0 as f64;
+
+            match tile.type_ {
+                TileType::Pellet => {
+                    // Regular pellet
+                    let pellet_x = tile_x + (tile_size - pellet_size) / 2.0;
+                    let pellet_y = tile_y + (tile_size - pellet_size) / 2.0;
+                    let pellet_circle = circle(pellet_x, pellet_y, pellet_size / 2.0);
+                    ellipse(PELLET_COLOR, pellet_circle, transform, graphics);
+                },
+                TileType::PowerPellet => {
+                    // Power pellet
+                    let power_x = tile_x + (tile_size - power_size) / 2.0;
+                    let power_y = tile_y + (tile_size - power_size) / 2.0;
+                    let power_circle = circle(power_x, power_y, power_size / 2.0);
+                    ellipse(POWER_PELLET_COLOR, power_circle, transform, graphics);
+                },
+                _ => {}
+            }
+        }
+
+        // Draw the bonus fruit
+        if let Some((fruit, (x, y))) = game.fruit() {
+            let fruit_circle = circle(((x * CELL_SIZE) as f64) + CELL_SIZE as f64 / 2.0, ((y * CELL_SIZE) as f64) + CELL_SIZE as f64 / 2.0, CELL_SIZE as f64 * 0.45);
+            ellipse(get_fruit_color(fruit), fruit_circle, transform, graphics);
+        }
+
+        // Draw the ghosts and Pacman (unchanged)
+        for ghost in game.ghosts() {
+            let square = rectangle::square(ghost.get_pixels_x() as f64, ghost.get_pixels_y() as f64, CELL_SIZE as f64);
+            rectangle(get_color_from_state(ghost), square, transform, graphics);
+        }
+
+        // Draw Pacman
+        let square = rectangle::square(pacman.get_pixels_x() as f64, pacman.get_pixels_y() as f64, CELL_SIZE as f64);
+        rectangle(PACMAN_COLOR, square, transform, graphics);
+
+        if *game.game_state() == GameState::Paused {
+            self.render_pause(game, c, graphics);
+        }
+    }
+}
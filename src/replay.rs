@@ -0,0 +1,187 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+use crate::constants::{ROSTER_FILE, RULES_FILE};
+use crate::enums::Direction;
+
+pub const REPLAY_VERSION: u32 = 3;
+const REPLAY_HEADER: &str = "pacman-replay";
+
+// A direction change and the simulation tick it was applied on
+#[derive(Clone, Copy, Debug)]
+pub struct ReplayInput {
+    pub tick: u64,
+    pub direction: Direction,
+}
+
+// Everything needed to reproduce a game: the seed, the map, the ghost roster, the rules and the inputs.
+//
+// File format (plain text, one entry per line):
+//   pacman-replay 3
+//   seed 1234
+//   map grid.map
+//   roster ghosts.roster
+//   rules arcade.rules
+//   120 Left
+//   305 Up
+pub struct Replay {
+    pub seed: u64,
+    pub map: String,
+    pub roster: String,
+    pub rules: String,
+    pub inputs: Vec<ReplayInput>,
+}
+
+fn direction_to_str(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "Up",
+        Direction::Left => "Left",
+        Direction::Down => "Down",
+        Direction::Right => "Right",
+    }
+}
+
+fn parse_direction(s: &str) -> Option<Direction> {
+    match s {
+        "Up" => Some(Direction::Up),
+        "Left" => Some(Direction::Left),
+        "Down" => Some(Direction::Down),
+        "Right" => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+fn invalid_data(line: usize, message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("replay line {}: {}", line, message))
+}
+
+impl Replay {
+    pub fn new(seed: u64, map: String, roster: String, rules: String) -> Self {
+        Replay { seed, map, roster, rules, inputs: Vec::new() }
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writeln!(writer, "{} {}", REPLAY_HEADER, REPLAY_VERSION)?;
+        writeln!(writer, "seed {}", self.seed)?;
+        writeln!(writer, "map {}", self.map)?;
+        writeln!(writer, "roster {}", self.roster)?;
+        writeln!(writer, "rules {}", self.rules)?;
+        for input in &self.inputs {
+            writeln!(writer, "{} {}", input.tick, direction_to_str(input.direction))?;
+        }
+
+        writer.flush()
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut lines = reader.lines();
+
+        // Header with the format version
+        let header = lines.next().transpose()?.unwrap_or_default();
+        let version = match header.split_once(' ') {
+            Some((REPLAY_HEADER, version)) => match version.trim().parse::<u32>() {
+                Ok(version @ 1..=REPLAY_VERSION) => version,
+                _ => return Err(invalid_data(1, &format!("unsupported replay version {}", version.trim()))),
+            },
+            _ => return Err(invalid_data(1, "not a replay file")),
+        };
+
+        let seed = match lines.next().transpose()?.as_deref().and_then(|l| l.strip_prefix("seed ")) {
+            Some(seed) => seed.trim().parse::<u64>().map_err(|_| invalid_data(2, "invalid seed"))?,
+            None => return Err(invalid_data(2, "missing seed")),
+        };
+
+        let map = match lines.next().transpose()?.as_deref().and_then(|l| l.strip_prefix("map ")) {
+            Some(map) => map.trim().to_string(),
+            None => return Err(invalid_data(3, "missing map")),
+        };
+
+        // Older replays were played with the default roster (before version 2) and rules (before 3)
+        let roster = if version >= 2 {
+            match lines.next().transpose()?.as_deref().and_then(|l| l.strip_prefix("roster ")) {
+                Some(roster) => roster.trim().to_string(),
+                None => return Err(invalid_data(4, "missing roster")),
+            }
+        } else {
+            ROSTER_FILE.to_string()
+        };
+        let rules = if version >= 3 {
+            match lines.next().transpose()?.as_deref().and_then(|l| l.strip_prefix("rules ")) {
+                Some(rules) => rules.trim().to_string(),
+                None => return Err(invalid_data(5, "missing rules")),
+            }
+        } else {
+            RULES_FILE.to_string()
+        };
+        let first_input_line = 3 + version as usize;
+
+        let mut replay = Replay::new(seed, map, roster, rules);
+
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            let line_number = index + first_input_line;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (tick, direction) = line
+                .trim()
+                .split_once(' ')
+                .ok_or_else(|| invalid_data(line_number, "expected `<tick> <direction>`"))?;
+            let tick = tick.parse::<u64>().map_err(|_| invalid_data(line_number, "invalid tick"))?;
+            let direction = parse_direction(direction.trim())
+                .ok_or_else(|| invalid_data(line_number, "invalid direction"))?;
+
+            replay.inputs.push(ReplayInput { tick, direction });
+        }
+
+        Ok(replay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{load_error, test_file};
+
+    #[test]
+    fn round_trips_through_a_file() {
+        let mut replay = Replay::new(42, "grid.map".to_string(), "ghosts.roster".to_string(), "custom.rules".to_string());
+        replay.inputs.push(ReplayInput { tick: 3, direction: Direction::Up });
+        replay.inputs.push(ReplayInput { tick: 120, direction: Direction::Right });
+
+        let path = test_file("replay-round-trip.replay", "");
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path).unwrap();
+
+        assert_eq!((loaded.seed, loaded.map.as_str(), loaded.roster.as_str(), loaded.rules.as_str()), (42, "grid.map", "ghosts.roster", "custom.rules"));
+        let inputs: Vec<(u64, Direction)> = loaded.inputs.iter().map(|input| (input.tick, input.direction)).collect();
+        assert_eq!(inputs, vec![(3, Direction::Up), (120, Direction::Right)]);
+    }
+
+    #[test]
+    fn older_versions_use_the_default_roster_and_rules() {
+        let v1 = Replay::load(&test_file("replay-v1.replay", "pacman-replay 1\nseed 7\nmap old.map\n10 Left\n")).unwrap();
+        assert_eq!((v1.roster.as_str(), v1.rules.as_str()), (ROSTER_FILE, RULES_FILE));
+        assert_eq!(v1.inputs.len(), 1);
+
+        let v2 = Replay::load(&test_file("replay-v2.replay", "pacman-replay 2\nseed 7\nmap old.map\nroster my.roster\n10 Down\n")).unwrap();
+        assert_eq!((v2.roster.as_str(), v2.rules.as_str()), ("my.roster", RULES_FILE));
+        assert_eq!(v2.inputs[0].direction, Direction::Down);
+    }
+
+    #[test]
+    fn rejects_bad_files_with_the_line() {
+        assert_eq!(load_error("replay-version.replay", "pacman-replay 9\n", Replay::load), "replay line 1: unsupported replay version 9");
+        assert_eq!(load_error("replay-header.replay", "hello\n", Replay::load), "replay line 1: not a replay file");
+        assert_eq!(load_error("replay-seed.replay", "pacman-replay 3\nseed many\n", Replay::load), "replay line 2: invalid seed");
+        assert_eq!(load_error("replay-rules.replay", "pacman-replay 3\nseed 1\nmap a\nroster b\n5 Up\n", Replay::load), "replay line 5: missing rules");
+        assert_eq!(
+            load_error("replay-input.replay", "pacman-replay 3\nseed 1\nmap a\nroster b\nrules c\n5 Sideways\n", Replay::load),
+            "replay line 6: invalid direction"
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+use crate::brain::{Bashful, GhostBrain, Pokey, Shadow, Speedy};
+use crate::enums::Targeting;
+use crate::grid::{Grid, Walkability};
+
+// Where a ghost spawns or scatters: a marker of the map, or a tile given directly
+#[derive(Clone, Debug, PartialEq)]
+pub enum RosterPos {
+    Marker(char),
+    Tile((i32, i32)),
+}
+
+// A roster that does not fit the map it is played on
+#[derive(Debug)]
+pub enum RosterError {
+    UnknownMarker { ghost: String, marker: char },
+    OutOfBounds { ghost: String, pos: (i32, i32) },
+    UnwalkableSpawn { ghost: String, pos: (i32, i32) },
+    UnknownPartner { ghost: String, partner: String },
+}
+
+impl fmt::Display for RosterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RosterError::UnknownMarker { ghost, marker } => write!(f, "{}: the map has no marker {:?}", ghost, marker),
+            RosterError::OutOfBounds { ghost, pos: (col, row) } => write!(f, "{}: tile {},{} is outside the map", ghost, col, row),
+            RosterError::UnwalkableSpawn { ghost, pos: (col, row) } => write!(f, "{}: spawn {},{} is not walkable", ghost, col, row),
+            RosterError::UnknownPartner { ghost, partner } => write!(f, "{}: no ghost named {} to partner with", ghost, partner),
+        }
+    }
+}
+
+impl Error for RosterError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Personality {
+    Shadow,
+    Speedy,
+    Bashful { partner: String },
+    Pokey,
+}
+
+impl Personality {
+    pub fn brain(&self) -> Box<dyn GhostBrain> {
+        match self {
+            Personality::Shadow => Box::new(Shadow),
+            Personality::Speedy => Box::new(Speedy),
+            Personality::Bashful { partner } => Box::new(Bashful { partner: partner.clone() }),
+            Personality::Pokey => Box::new(Pokey),
+        }
+    }
+
+    // Character shown before the ghost name on the title screen
+    pub fn character(&self) -> &'static str {
+        match self {
+            Personality::Shadow => "Shadow",
+            Personality::Speedy => "Speedy",
+            Personality::Bashful { .. } => "Bashful",
+            Personality::Pokey => "Pokey",
+        }
+    }
+}
+
+// One ghost of the roster
+#[derive(Clone, Debug)]
+pub struct GhostConfig {
+    pub name: String,
+    pub color: [f32; 4],
+    pub spawn: RosterPos,
+    pub corner: RosterPos,
+    pub personality: Personality,
+    pub dot_limits: Vec<usize>, // Per level, the last value holds for the later levels
+    pub global_dot_limit: usize,
+    pub targeting: Targeting,
+    pub elroy: bool,
+}
+
+impl GhostConfig {
+    // Spawn tile on the grid: a marker of the map or a walkable tile
+    pub fn resolve_spawn(&self, grid: &Grid) -> Result<(i32, i32), RosterError> {
+        let pos = match self.spawn {
+            RosterPos::Marker(marker) => grid
+                .markers()
+                .ghost_spawn(marker)
+                .ok_or_else(|| RosterError::UnknownMarker { ghost: self.name.clone(), marker })?,
+            RosterPos::Tile(pos) => pos,
+        };
+
+        match grid.get_tile(pos) {
+            None => Err(RosterError::OutOfBounds { ghost: self.name.clone(), pos }),
+            Some(tile) if !Walkability::EatenGhost.allows(tile) => Err(RosterError::UnwalkableSpawn { ghost: self.name.clone(), pos }),
+            Some(_) => Ok(pos),
+        }
+    }
+
+    // Scatter corner on the grid, it may be a wall as it is only a target
+    pub fn resolve_corner(&self, grid: &Grid) -> Result<(i32, i32), RosterError> {
+        let pos = match self.corner {
+            // Corners are keyed by the uppercase marker, like the spawns
+            RosterPos::Marker(marker) => grid
+                .markers()
+                .scatter_corner(marker.to_ascii_uppercase())
+                .ok_or_else(|| RosterError::UnknownMarker { ghost: self.name.clone(), marker })?,
+            RosterPos::Tile(pos) => pos,
+        };
+
+        if grid.in_bounds(pos) {
+            Ok(pos)
+        } else {
+            Err(RosterError::OutOfBounds { ghost: self.name.clone(), pos })
+        }
+    }
+}
+
+// The ghosts of a game, in release order: the first one leaves the house first.
+//
+// File format (plain text, one ghost per line, `#` starts a comment):
+//   name=Inky color=0.0,1.0,1.0 spawn=I corner=i brain=bashful partner=Blinky dots=30,0 global-dots=17
+//
+//   name=         shown on the title screen, other ghosts refer to it
+//   color=        r,g,b or r,g,b,a between 0 and 1
+//   spawn=        spawn marker of the map (B, K, I, C) or the col,row of a walkable tile
+//   corner=       scatter corner marker of the map (b, k, i, c) or col,row inside the map
+//   brain=        shadow, speedy, bashful or pokey
+//   partner=      ghost whose position bashful uses, the first ghost by default
+//   dots=         pellets to wait for in the house, per level (default 0)
+//   global-dots=  pellets to wait for after Pacman died (default 0)
+//   targeting=    manhattan (default) or maze
+//   elroy         turns into Cruise Elroy when the maze empties
+#[derive(Clone, Debug)]
+pub struct Roster {
+    pub path: String,
+    pub ghosts: Vec<GhostConfig>,
+}
+
+fn invalid_data(line: usize, message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("roster line {}: {}", line, message))
+}
+
+fn parse_numbers<T: std::str::FromStr>(value: &str) -> Option<Vec<T>> {
+    value.split(',').map(|n| n.trim().parse().ok()).collect()
+}
+
+fn parse_pos(value: &str) -> Option<RosterPos> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(marker), None) => Some(RosterPos::Marker(marker)),
+        _ => match *parse_numbers::<i32>(value)?.as_slice() {
+            [col, row] => Some(RosterPos::Tile((col, row))),
+            _ => None,
+        },
+    }
+}
+
+fn parse_color(value: &str) -> Option<[f32; 4]> {
+    match *parse_numbers::<f32>(value)?.as_slice() {
+        [r, g, b] => Some([r, g, b, 1.0]),
+        [r, g, b, a] => Some([r, g, b, a]),
+        _ => None,
+    }
+}
+
+// Reads one ghost line, the partner of bashful is left empty when not given
+fn parse_ghost(line: &str, line_number: usize) -> io::Result<GhostConfig> {
+    let error = |message: &str| invalid_data(line_number, message);
+
+    let (mut name, mut color, mut spawn, mut corner, mut brain) = (None, None, None, None, None);
+    let mut partner = String::new();
+    let mut dot_limits = vec![0];
+    let mut global_dot_limit = 0;
+    let mut targeting = Targeting::Manhattan;
+    let mut elroy = false;
+
+    for field in line.split_whitespace() {
+        let Some((key, value)) = field.split_once('=') else {
+            match field {
+                "elroy" => elroy = true,
+                _ => return Err(error(&format!("unknown flag `{}`", field))),
+            }
+            continue;
+        };
+
+        match key {
+            "name" => name = Some(value.to_string()),
+            "color" => color = Some(parse_color(value).ok_or_else(|| error("invalid color"))?),
+            "spawn" => spawn = Some(parse_pos(value).ok_or_else(|| error("invalid spawn"))?),
+            "corner" => corner = Some(parse_pos(value).ok_or_else(|| error("invalid corner"))?),
+            "brain" => brain = Some(value.to_string()),
+            "partner" => partner = value.to_string(),
+            "dots" => dot_limits = parse_numbers(value).filter(|l: &Vec<usize>| !l.is_empty()).ok_or_else(|| error("invalid dots"))?,
+            "global-dots" => global_dot_limit = value.parse().map_err(|_| error("invalid global-dots"))?,
+            "targeting" => targeting = match value {
+                "manhattan" => Targeting::Manhattan,
+                "maze" => Targeting::MazeDistance,
+                _ => return Err(error("targeting must be manhattan or maze")),
+            },
+            _ => return Err(error(&format!("unknown key `{}`", key))),
+        }
+    }
+
+    let personality = match brain.as_deref() {
+        Some("shadow") => Personality::Shadow,
+        Some("speedy") => Personality::Speedy,
+        Some("bashful") => Personality::Bashful { partner },
+        Some("pokey") => Personality::Pokey,
+        Some(other) => return Err(error(&format!("unknown brain `{}`", other))),
+        None => return Err(error("missing brain")),
+    };
+
+    Ok(GhostConfig {
+        name: name.ok_or_else(|| error("missing name"))?,
+        color: color.ok_or_else(|| error("missing color"))?,
+        spawn: spawn.ok_or_else(|| error("missing spawn"))?,
+        corner: corner.ok_or_else(|| error("missing corner"))?,
+        personality,
+        dot_limits,
+        global_dot_limit,
+        targeting,
+        elroy,
+    })
+}
+
+impl Roster {
+    pub fn load(path: &str) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut ghosts: Vec<GhostConfig> = Vec::new();
+
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let ghost = parse_ghost(line, line_number)?;
+            if ghosts.iter().any(|other| other.name == ghost.name) {
+                return Err(invalid_data(line_number, &format!("duplicate ghost name `{}`", ghost.name)));
+            }
+            ghosts.push(ghost);
+        }
+
+        let Some(first) = ghosts.first().map(|ghost| ghost.name.clone()) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "the roster has no ghost"));
+        };
+
+        // Bashful ghosts follow the first ghost unless told otherwise
+        for ghost in &mut ghosts {
+            if let Personality::Bashful { partner } = &mut ghost.personality {
+                if partner.is_empty() {
+                    *partner = first.clone();
+                }
+            }
+        }
+
+        Ok(Roster { path: path.to_string(), ghosts })
+    }
+
+    // Every bashful ghost needs its partner in the roster
+    pub fn check_partners(&self) -> Result<(), RosterError> {
+        for ghost in &self.ghosts {
+            if let Personality::Bashful { partner } = &ghost.personality {
+                if !self.ghosts.iter().any(|other| &other.name == partner) {
+                    return Err(RosterError::UnknownPartner { ghost: ghost.name.clone(), partner: partner.clone() });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{load_error, test_file};
+
+    #[test]
+    fn loads_the_default_roster() {
+        let roster = Roster::load("ghosts.roster").unwrap();
+        let names: Vec<&str> = roster.ghosts.iter().map(|ghost| ghost.name.as_str()).collect();
+        assert_eq!(names, ["Blinky", "Pinky", "Inky", "Clyde"]);
+        assert!(roster.ghosts[0].elroy);
+        assert_eq!(roster.ghosts[2].personality, Personality::Bashful { partner: "Blinky".to_string() });
+        assert_eq!(roster.ghosts[3].dot_limits, [60, 50, 0]);
+    }
+
+    #[test]
+    fn parses_every_field() {
+        let path = test_file(
+            "roster-fields.roster",
+            "# comment\nname=Red color=1,0,0 spawn=3,4 corner=b brain=shadow\n\nname=Cyan color=0,1,1,0.5 spawn=I corner=-1,40 brain=bashful dots=5,1 global-dots=9 targeting=maze\n",
+        );
+        let roster = Roster::load(&path).unwrap();
+        let cyan = &roster.ghosts[1];
+
+        assert_eq!(roster.ghosts[0].spawn, RosterPos::Tile((3, 4)));
+        assert_eq!(roster.ghosts[0].corner, RosterPos::Marker('b'));
+        assert_eq!(cyan.color, [0.0, 1.0, 1.0, 0.5]);
+        assert_eq!(cyan.personality, Personality::Bashful { partner: "Red".to_string() }); // The first ghost by default
+        assert_eq!((cyan.dot_limits.as_slice(), cyan.global_dot_limit), ([5, 1].as_slice(), 9));
+        assert_eq!(cyan.targeting, Targeting::MazeDistance);
+    }
+
+    #[test]
+    fn rejects_bad_lines_with_the_line() {
+        let ghost = "name=Red color=1,0,0 spawn=B corner=b brain=shadow";
+
+        assert_eq!(load_error("roster-empty.roster", "# nothing\n", Roster::load), "the roster has no ghost");
+        assert_eq!(load_error("roster-brain.roster", "name=Red color=1,0,0 spawn=B corner=b\n", Roster::load), "roster line 1: missing brain");
+        assert_eq!(load_error("roster-key.roster", &format!("{} speed=2\n", ghost), Roster::load), "roster line 1: unknown key `speed`");
+        assert_eq!(
+            load_error("roster-duplicate.roster", &format!("{}\n{}\n", ghost, ghost), Roster::load),
+            "roster line 2: duplicate ghost name `Red`"
+        );
+    }
+
+    #[test]
+    fn resolves_against_the_map() {
+        let grid = Grid::new(&test_file("roster-grid.map", "k####\n#B.P#\n#####\n"), 16).unwrap();
+        let roster = |line: &str| Roster::load(&test_file("roster-resolve.roster", line)).unwrap();
+
+        let good = roster("name=Red color=1,0,0 spawn=B corner=k brain=shadow\n");
+        assert_eq!(good.ghosts[0].resolve_spawn(&grid).unwrap(), (1, 1));
+        assert_eq!(good.ghosts[0].resolve_corner(&grid).unwrap(), (0, 0)); // Corners may be walls
+
+        let wall = roster("name=Red color=1,0,0 spawn=0,1 corner=k brain=shadow\n");
+        assert!(matches!(wall.ghosts[0].resolve_spawn(&grid), Err(RosterError::UnwalkableSpawn { .. })));
+        let outside = roster("name=Red color=1,0,0 spawn=B corner=9,9 brain=shadow\n");
+        assert!(matches!(outside.ghosts[0].resolve_corner(&grid), Err(RosterError::OutOfBounds { .. })));
+        let marker = roster("name=Red color=1,0,0 spawn=C corner=k brain=shadow\n");
+        assert!(matches!(marker.ghosts[0].resolve_spawn(&grid), Err(RosterError::UnknownMarker { marker: 'C', .. })));
+
+        let partner = roster("name=Red color=1,0,0 spawn=B corner=k brain=bashful partner=Blue\n");
+        assert!(matches!(partner.check_partners(), Err(RosterError::UnknownPartner { .. })));
+    }
+}
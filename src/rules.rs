@@ -0,0 +1,177 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+use crate::constants::EATEN_GHOSTS_MULTIPLIERS;
+use crate::enums::Fruit;
+
+// Points and lives of a game.
+//
+// File format (plain text, one `key=value` per line, `#` starts a comment, missing keys keep
+// the arcade value):
+//   pellet=10
+//   power-pellet=50
+//   ghosts=200,400,800,1600      points for the 1st, 2nd... ghost eaten on one power pellet
+//   fruits=100,300,500,700,1000,2000,3000,5000    cherry, strawberry, orange, apple, melon, galaxian, bell, key
+//   extra-life=10000             score awarding an extra life, 0 for none
+//   extra-life-repeat=no         yes for another life every time the score gains extra-life points
+//   max-lives=3                  lives can not go above this with the extra lives
+#[derive(Clone, Debug)]
+pub struct Ruleset {
+    pub path: String,
+    pub pellet_points: i32,
+    pub power_pellet_points: i32,
+    pub ghost_points: Vec<i32>,
+    pub fruit_points: [i32; 8],
+    pub extra_life_score: i32,
+    pub extra_life_repeat: bool,
+    pub max_lives: i32,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Ruleset {
+            path: String::new(),
+            pellet_points: 10,
+            power_pellet_points: 50,
+            ghost_points: EATEN_GHOSTS_MULTIPLIERS.to_vec(),
+            fruit_points: [100, 300, 500, 700, 1000, 2000, 3000, 5000],
+            extra_life_score: 10000,
+            extra_life_repeat: false,
+            max_lives: 3,
+        }
+    }
+}
+
+fn invalid_data(line: usize, message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("rules line {}: {}", line, message))
+}
+
+fn parse_points(value: &str) -> Option<Vec<i32>> {
+    value.split(',').map(|n| n.trim().parse().ok()).collect()
+}
+
+impl Ruleset {
+    pub fn load(path: &str) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut rules = Ruleset { path: path.to_string(), ..Ruleset::default() };
+
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let error = |message: &str| invalid_data(line_number, message);
+            let (key, value) = line.split_once('=').ok_or_else(|| error("expected `key=value`"))?;
+            let (key, value) = (key.trim(), value.trim());
+            let number = || value.parse::<i32>().map_err(|_| error(&format!("invalid {}", key)));
+
+            match key {
+                "pellet" => rules.pellet_points = number()?,
+                "power-pellet" => rules.power_pellet_points = number()?,
+                "ghosts" => {
+                    rules.ghost_points = parse_points(value)
+                        .filter(|points| !points.is_empty())
+                        .ok_or_else(|| error("invalid ghosts"))?;
+                },
+                "fruits" => {
+                    rules.fruit_points = parse_points(value)
+                        .and_then(|points| points.try_into().ok())
+                        .ok_or_else(|| error("fruits needs the 8 fruit values"))?;
+                },
+                "extra-life" => rules.extra_life_score = number()?,
+                "extra-life-repeat" => {
+                    rules.extra_life_repeat = match value {
+                        "yes" => true,
+                        "no" => false,
+                        _ => return Err(error("extra-life-repeat must be yes or no")),
+                    };
+                },
+                "max-lives" => rules.max_lives = number()?,
+                _ => return Err(error(&format!("unknown key `{}`", key))),
+            }
+        }
+
+        Ok(rules)
+    }
+
+    // Points for the nth ghost eaten on one power pellet, the last value past the end of the table
+    pub fn ghost_points(&self, eaten: usize) -> i32 {
+        self.ghost_points.get(eaten).or(self.ghost_points.last()).copied().unwrap_or(0)
+    }
+
+    pub fn fruit_points(&self, fruit: Fruit) -> i32 {
+        let index = match fruit {
+            Fruit::Cherry => 0,
+            Fruit::Strawberry => 1,
+            Fruit::Orange => 2,
+            Fruit::Apple => 3,
+            Fruit::Melon => 4,
+            Fruit::Galaxian => 5,
+            Fruit::Bell => 6,
+            Fruit::Key => 7,
+        };
+        self.fruit_points[index]
+    }
+
+    // Extra lives earned by going from one score to the other
+    pub fn extra_lives(&self, old_score: i32, new_score: i32) -> i32 {
+        if self.extra_life_score <= 0 {
+            return 0;
+        }
+
+        if self.extra_life_repeat {
+            new_score / self.extra_life_score - old_score / self.extra_life_score
+        } else if old_score < self.extra_life_score && new_score >= self.extra_life_score {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{load_error, test_file};
+
+    #[test]
+    fn missing_keys_keep_the_arcade_values() {
+        let rules = Ruleset::load(&test_file("rules-partial.rules", "pellet=20 # doubled\nextra-life-repeat=yes\n")).unwrap();
+        let arcade = Ruleset::load("arcade.rules").unwrap();
+
+        assert_eq!(rules.pellet_points, 20);
+        assert!(rules.extra_life_repeat);
+        assert_eq!(rules.power_pellet_points, arcade.power_pellet_points);
+        assert_eq!(rules.ghost_points, arcade.ghost_points);
+        assert_eq!(rules.max_lives, arcade.max_lives);
+    }
+
+    #[test]
+    fn rejects_bad_lines_with_the_line() {
+        assert_eq!(load_error("rules-key.rules", "pellet=10\nbonus=3\n", Ruleset::load), "rules line 2: unknown key `bonus`");
+        assert_eq!(load_error("rules-fruits.rules", "fruits=1,2,3\n", Ruleset::load), "rules line 1: fruits needs the 8 fruit values");
+        assert_eq!(load_error("rules-number.rules", "max-lives=many\n", Ruleset::load), "rules line 1: invalid max-lives");
+    }
+
+    #[test]
+    fn ghost_points_stay_on_the_last_value() {
+        let rules = Ruleset { ghost_points: vec![200, 400], ..Ruleset::default() };
+        assert_eq!([rules.ghost_points(0), rules.ghost_points(1), rules.ghost_points(5)], [200, 400, 400]);
+    }
+
+    #[test]
+    fn awards_extra_lives_once_or_on_every_threshold() {
+        let once = Ruleset { extra_life_score: 1000, ..Ruleset::default() };
+        assert_eq!(once.extra_lives(900, 1000), 1);
+        assert_eq!(once.extra_lives(1000, 2500), 0);
+
+        let repeat = Ruleset { extra_life_repeat: true, ..once.clone() };
+        assert_eq!(repeat.extra_lives(900, 3100), 3);
+
+        let none = Ruleset { extra_life_score: 0, ..Ruleset::default() };
+        assert_eq!(none.extra_lives(0, 99999), 0);
+    }
+}
@@ -1,46 +1,58 @@
-use crate::constants::{GRID_WIDTH, GRID_HEIGHT};
-
 pub fn manhattan_distance(a: (i32, i32), b: (i32, i32)) -> f32 {
     ((a.0 - b.0).abs() + (a.1 - b.1).abs()) as f32
 }
 
-fn check_bounds(pos: (i32, i32)) -> bool {
-    pos.0 >= 0 && pos.0 < GRID_WIDTH && pos.1 >= 0 && pos.1 < GRID_HEIGHT
+pub fn get_speed_for_level(base_speed: f64, level: usize, min_speed: f64) -> f64 {
+    let scale = 1.0 - (level as f64 * 0.03);
+    (base_speed * scale).max(min_speed)
 }
 
-pub fn find_neighbors(pos: (i32, i32)) -> Vec<(i32, i32)> {
-    let mut neighbors = Vec::new();
+// Ghost speeds of a level, as fractions of full speed (the arcade speed table)
+#[derive(Clone, Copy, Debug)]
+pub struct GhostSpeeds {
+    pub normal: f64,
+    pub frightened: f64,
+    pub tunnel: f64,
+    pub eaten: f64,
+    pub elroy_1: f64,
+    pub elroy_2: f64,
+}
 
-    for i in -1..=1 {
-        for j in -1..=1 {
-            if i == 0 && j == 0 {
-                // Skip the current position
-                continue;
-            }
+pub fn get_ghost_speeds(level: usize) -> GhostSpeeds {
+    match level {
+        0 | 1 => GhostSpeeds { normal: 0.75, frightened: 0.50, tunnel: 0.40, eaten: 1.6, elroy_1: 0.80, elroy_2: 0.85 },
+        2..=4 => GhostSpeeds { normal: 0.85, frightened: 0.55, tunnel: 0.45, eaten: 1.6, elroy_1: 0.90, elroy_2: 0.95 },
+        _ => GhostSpeeds { normal: 0.95, frightened: 0.60, tunnel: 0.50, eaten: 1.6, elroy_1: 1.00, elroy_2: 1.05 },
+    }
+}
 
-            let new_pos = (pos.0 + i, pos.1 + j);
-            if check_bounds(new_pos) {
-                neighbors.push(new_pos);
-            } else if new_pos.0 < 0 {
-                // Wrap around horizontally
-                neighbors.push((GRID_WIDTH - 1, new_pos.1));
-            } else if new_pos.0 >= GRID_WIDTH {
-                // Wrap around horizontally
-                neighbors.push((0, new_pos.1));
-            } else if new_pos.1 < 0 {
-                // Wrap around vertically
-                neighbors.push((new_pos.0, GRID_HEIGHT - 1));
-            } else if new_pos.1 >= GRID_HEIGHT {
-                // Wrap around vertically
-                neighbors.push((new_pos.0, 0));
-            }
-        }
+// Pellets left when Blinky turns into Cruise Elroy (first stage), the second stage starts at half
+pub fn get_elroy_pellets_left(level: usize) -> usize {
+    match level {
+        0 | 1 => 20,
+        2 => 30,
+        3..=5 => 40,
+        6..=8 => 50,
+        9..=11 => 60,
+        12..=14 => 80,
+        15..=18 => 100,
+        _ => 120,
     }
+}
 
-    neighbors
+// Writes a file in the temp directory for a test, every test picks its own name
+#[cfg(test)]
+pub(crate) fn test_file(name: &str, contents: &str) -> String {
+    let path = std::env::temp_dir().join(format!("pacman-rs-{}-{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path.to_string_lossy().into_owned()
 }
 
-pub fn get_speed_for_level(base_speed: f64, level: usize, min_speed: f64) -> f64 {
-    let scale = 1.0 - (level as f64 * 0.03);
-    (base_speed * scale).max(min_speed)
+// Loads a test file that should be rejected and returns the message
+#[cfg(test)]
+pub(crate) fn load_error<T, E: std::fmt::Display>(name: &str, contents: &str, load: impl Fn(&str) -> Result<T, E>) -> String {
+    match load(&test_file(name, contents)) {
+        Ok(_) => panic!("{} should not load", name),
+        Err(error) => error.to_string(),
+    }
 }